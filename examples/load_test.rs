@@ -0,0 +1,124 @@
+//! Load test for per-fork locking.
+//!
+//! Sends the same number of SOL transfers twice: first with every worker
+//! sharing one fork, then with each worker on a fork of its own. Workers on
+//! the shared fork queue up behind its lock, while separate forks only meet
+//! at the manager's map, so the second run should scale with the workers
+//! up to the number of CPU cores.
+//!
+//! ```text
+//! cargo run --release --example load_test -- [workers] [transfers_per_worker]
+//! ```
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use solana_fork_engine::{sol_to_lamports, ForkManager};
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_system_interface::instruction::transfer;
+use solana_transaction::Transaction;
+
+const DEFAULT_WORKERS: usize = 8;
+const DEFAULT_TRANSFERS: usize = 200;
+/// Lamports per transfer, above the rent-exempt minimum of an empty account
+const TRANSFER_LAMPORTS: u64 = 1_000_000;
+
+fn main() {
+    let mut args = std::env::args()
+        .skip(1)
+        .map(|arg| arg.parse::<usize>().expect("arguments must be positive numbers"));
+    let workers = args.next().unwrap_or(DEFAULT_WORKERS).max(1);
+    let transfers = args.next().unwrap_or(DEFAULT_TRANSFERS).max(1);
+
+    println!("{} worker(s) x {} transfer(s)", workers, transfers);
+    println!();
+
+    let manager = ForkManager::new();
+
+    let shared_fork_id = manager
+        .create_fork("load-test-shared".to_string())
+        .expect("Failed to create fork");
+
+    // Untimed round so neither run pays for first-use costs
+    run(&manager, &vec![shared_fork_id.clone(); workers], transfers);
+
+    let single = run(&manager, &vec![shared_fork_id; workers], transfers);
+    report("one shared fork", workers * transfers, single);
+
+    let fork_ids: Vec<String> = (0..workers)
+        .map(|worker| {
+            manager
+                .create_fork(format!("load-test-{}", worker))
+                .expect("Failed to create fork")
+        })
+        .collect();
+    let parallel = run(&manager, &fork_ids, transfers);
+    report("one fork per worker", workers * transfers, parallel);
+
+    println!();
+    println!("Speedup: {:.2}x", single.as_secs_f64() / parallel.as_secs_f64());
+}
+
+/// Run one thread per entry of `fork_ids`, each sending `transfers` transfers
+/// to its fork, and return the wall time of the whole run
+fn run(manager: &ForkManager, fork_ids: &[String], transfers: usize) -> Duration {
+    // Fund one payer per worker up front so only the transfers are timed
+    let payers: Vec<Keypair> = fork_ids
+        .iter()
+        .map(|fork_id| {
+            let payer = Keypair::new();
+            manager
+                .get_fork(fork_id)
+                .expect("Fork not found")
+                .blocking_write()
+                .add_balance(&payer.pubkey(), sol_to_lamports(transfers as f64))
+                .expect("Failed to fund payer");
+            payer
+        })
+        .collect();
+
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        for (fork_id, payer) in fork_ids.iter().zip(&payers) {
+            scope.spawn(move || {
+                let recipient = Pubkey::new_unique();
+
+                for transfer_index in 0..transfers {
+                    // Look the fork up for every transfer, as request handlers do
+                    let fork = manager.get_fork(fork_id).expect("Fork not found");
+                    let mut fork = fork.blocking_write();
+
+                    // Distinct amounts keep signatures unique under one blockhash
+                    let instruction = transfer(
+                        &payer.pubkey(),
+                        &recipient,
+                        TRANSFER_LAMPORTS + transfer_index as u64,
+                    );
+                    let message = Message::new(&[instruction], Some(&payer.pubkey()));
+                    let transaction = Transaction::new(&[payer], message, fork.latest_blockhash());
+
+                    let result = fork
+                        .send_transaction(transaction, false)
+                        .expect("Failed to send transfer");
+                    assert!(result.success, "Transfer failed: {:?}", result.error);
+                }
+            });
+        }
+    });
+
+    start.elapsed()
+}
+
+fn report(label: &str, transactions: usize, elapsed: Duration) {
+    println!(
+        "{:<20} {:>6} tx in {:>8.2?}  {:>10.0} tx/s",
+        label,
+        transactions,
+        elapsed,
+        transactions as f64 / elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,476 @@
+use std::path::Path;
+use std::time::Duration;
+
+use toml_edit::{DocumentMut, Item, Value};
+
+use crate::constants::{
+    DEFAULT_ACCOUNT_CACHE_MAX_AGE_SECONDS, DEFAULT_ACCOUNT_CACHE_MAX_ENTRIES, DEFAULT_CLEANUP_INTERVAL_SECONDS, DEFAULT_CONFIG_FILE, DEFAULT_DATA_DIR, DEFAULT_FORK_LIFETIME_SECONDS,
+    DEFAULT_RPC_MAX_RETRIES, DEFAULT_RPC_RETRY_BACKOFF_MS, DEFAULT_RPC_TIMEOUT_SECONDS, DEFAULT_RPC_URL,
+    DEFAULT_SERVER_ADDR,
+};
+
+/// Limits applied to every fork individually
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ForkLimits {
+    /// Snapshots kept per fork; `None` is unlimited
+    pub max_snapshots: Option<usize>,
+    /// Transactions a fork executes before refusing more; `None` is unlimited
+    pub max_transactions: Option<u64>,
+}
+
+/// Timeouts and retries for requests to the upstream RPC
+#[derive(Debug, Clone, Copy)]
+pub struct RpcSettings {
+    /// Time allowed for a single request
+    pub timeout: Duration,
+    /// Retries of a request that failed with 429, a 5xx status, a timeout or a connection error
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every retry after it
+    pub retry_backoff: Duration,
+}
+
+impl Default for RpcSettings {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(DEFAULT_RPC_TIMEOUT_SECONDS),
+            max_retries: DEFAULT_RPC_MAX_RETRIES,
+            retry_backoff: Duration::from_millis(DEFAULT_RPC_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
+/// Size, staleness and backing file of the account cache shared by all upstream clients
+#[derive(Debug, Clone)]
+pub struct AccountCacheSettings {
+    /// Accounts kept at once, least recently used evicted first; 0 disables the cache
+    pub max_entries: usize,
+    /// Age after which a cached account is fetched again; `None` keeps it until evicted
+    pub max_age: Option<Duration>,
+    /// File the cache is loaded from at startup and saved to periodically; empty keeps it in memory only
+    pub path: String,
+}
+
+impl Default for AccountCacheSettings {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_ACCOUNT_CACHE_MAX_ENTRIES,
+            max_age: Some(Duration::from_secs(DEFAULT_ACCOUNT_CACHE_MAX_AGE_SECONDS)),
+            path: String::new(),
+        }
+    }
+}
+
+/// Server configuration. Each setting is taken from, in order of precedence,
+/// a CLI flag, an environment variable, the TOML config file, or its default.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub server_addr: String,
+    /// PubSub WebSocket address; `None` derives it from `server_addr` (port + 1)
+    pub pubsub_addr: Option<String>,
+    /// Directory forks are persisted to; empty disables persistence
+    pub data_dir: String,
+    /// Upstream used when a request does not name an endpoint: an RPC URL, or
+    /// `file://<dir>` to serve a fixture directory
+    pub rpc_url: String,
+    pub rpc: RpcSettings,
+    /// Fixture directory everything fetched from upstream is recorded into; empty disables recording
+    pub rpc_record_dir: String,
+    /// Directory under which requests may name `file://` fixture endpoints; empty rejects them
+    pub rpc_fixture_root: String,
+    pub account_cache: AccountCacheSettings,
+    pub fork_lifetime: Duration,
+    pub cleanup_interval: Duration,
+    /// Forks alive at once; `None` is unlimited
+    pub max_forks: Option<usize>,
+    pub fork_limits: ForkLimits,
+}
+
+/// A configurable value and the names it goes by in each source.
+/// `key` is dotted for settings nested in a TOML table.
+struct Setting {
+    key: &'static str,
+    env: &'static str,
+    flag: &'static str,
+    help: &'static str,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting { key: "server_addr", env: "SERVER_ADDR", flag: "--server-addr", help: "HTTP bind address" },
+    Setting {
+        key: "pubsub_addr",
+        env: "PUBSUB_ADDR",
+        flag: "--pubsub-addr",
+        help: "PubSub bind address (default: server port + 1)",
+    },
+    Setting {
+        key: "data_dir",
+        env: "FORK_DATA_DIR",
+        flag: "--data-dir",
+        help: "Fork persistence directory, empty disables",
+    },
+    Setting { key: "rpc_url", env: "SOLANA_RPC_URL", flag: "--rpc-url", help: "Default upstream RPC endpoint" },
+    Setting {
+        key: "rpc.timeout",
+        env: "RPC_TIMEOUT",
+        flag: "--rpc-timeout",
+        help: "Upstream RPC request timeout in seconds",
+    },
+    Setting {
+        key: "rpc.max_retries",
+        env: "RPC_MAX_RETRIES",
+        flag: "--rpc-max-retries",
+        help: "Retries of failed upstream RPC requests",
+    },
+    Setting {
+        key: "rpc.retry_backoff_ms",
+        env: "RPC_RETRY_BACKOFF_MS",
+        flag: "--rpc-retry-backoff-ms",
+        help: "First upstream RPC retry delay in ms, doubles",
+    },
+    Setting {
+        key: "rpc.record_dir",
+        env: "RPC_RECORD_DIR",
+        flag: "--rpc-record-dir",
+        help: "Record upstream fetches as fixtures here",
+    },
+    Setting {
+        key: "rpc.fixture_root",
+        env: "RPC_FIXTURE_ROOT",
+        flag: "--rpc-fixture-root",
+        help: "Allow file:// request endpoints under here",
+    },
+    Setting {
+        key: "account_cache.max_entries",
+        env: "ACCOUNT_CACHE_MAX_ENTRIES",
+        flag: "--account-cache-max-entries",
+        help: "Upstream accounts cached, 0 disables the cache",
+    },
+    Setting {
+        key: "account_cache.max_age",
+        env: "ACCOUNT_CACHE_MAX_AGE",
+        flag: "--account-cache-max-age",
+        help: "Seconds before a cached account is refetched, 0 never",
+    },
+    Setting {
+        key: "account_cache.path",
+        env: "ACCOUNT_CACHE_PATH",
+        flag: "--account-cache-path",
+        help: "Account cache file, empty keeps it in memory",
+    },
+    Setting { key: "fork_lifetime", env: "FORK_LIFETIME", flag: "--fork-lifetime", help: "Fork lifetime in seconds" },
+    Setting {
+        key: "cleanup_interval",
+        env: "CLEANUP_INTERVAL",
+        flag: "--cleanup-interval",
+        help: "Seconds between expired fork cleanups",
+    },
+    Setting { key: "max_forks", env: "MAX_FORKS", flag: "--max-forks", help: "Maximum live forks, 0 is unlimited" },
+    Setting {
+        key: "fork_limits.max_snapshots",
+        env: "MAX_SNAPSHOTS_PER_FORK",
+        flag: "--max-snapshots-per-fork",
+        help: "Maximum snapshots per fork, 0 is unlimited",
+    },
+    Setting {
+        key: "fork_limits.max_transactions",
+        env: "MAX_TRANSACTIONS_PER_FORK",
+        flag: "--max-transactions-per-fork",
+        help: "Maximum transactions per fork, 0 is unlimited",
+    },
+];
+
+/// Environment variable naming the config file
+const CONFIG_ENV: &str = "FORK_ENGINE_CONFIG";
+/// CLI flag naming the config file
+const CONFIG_FLAG: &str = "--config";
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server_addr: DEFAULT_SERVER_ADDR.to_string(),
+            pubsub_addr: None,
+            data_dir: DEFAULT_DATA_DIR.to_string(),
+            rpc_url: DEFAULT_RPC_URL.to_string(),
+            rpc: RpcSettings::default(),
+            rpc_record_dir: String::new(),
+            rpc_fixture_root: String::new(),
+            account_cache: AccountCacheSettings::default(),
+            fork_lifetime: Duration::from_secs(DEFAULT_FORK_LIFETIME_SECONDS),
+            cleanup_interval: Duration::from_secs(DEFAULT_CLEANUP_INTERVAL_SECONDS),
+            max_forks: None,
+            fork_limits: ForkLimits::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from the process arguments and environment.
+    /// Prints usage and exits on `--help`.
+    pub fn load() -> Result<Self, String> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+            println!("{}", Self::usage());
+            std::process::exit(0);
+        }
+
+        Self::from_sources(&args, |name| std::env::var(name).ok())
+    }
+
+    /// Build a configuration from CLI arguments and an environment lookup,
+    /// reading the config file they point to (or `fork-engine.toml` if present)
+    pub fn from_sources(args: &[String], env: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let flags = parse_flags(args)?;
+        let mut config = Self::default();
+
+        let explicit_file = flags
+            .iter()
+            .find(|(flag, _)| flag == CONFIG_FLAG)
+            .map(|(_, path)| path.clone())
+            .or_else(|| env(CONFIG_ENV));
+        match explicit_file {
+            Some(path) => config.apply_file(Path::new(&path))?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => config.apply_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            None => {}
+        }
+
+        for setting in SETTINGS {
+            if let Some(value) = env(setting.env) {
+                config.set(setting.key, &value).map_err(|e| format!("{}: {}", setting.env, e))?;
+            }
+        }
+
+        for (flag, value) in &flags {
+            if flag == CONFIG_FLAG {
+                continue;
+            }
+            let setting = SETTINGS
+                .iter()
+                .find(|setting| setting.flag == flag)
+                .ok_or_else(|| format!("Unknown flag {}\n\n{}", flag, Self::usage()))?;
+            config.set(setting.key, value).map_err(|e| format!("{}: {}", flag, e))?;
+        }
+
+        config.pubsub_addr()?;
+        Ok(config)
+    }
+
+    /// PubSub bind address, defaulting to the server port + 1 where Solana
+    /// clients look for it
+    pub fn pubsub_addr(&self) -> Result<String, String> {
+        if let Some(pubsub_addr) = &self.pubsub_addr {
+            return Ok(pubsub_addr.clone());
+        }
+
+        match self.server_addr.parse::<std::net::SocketAddr>() {
+            Ok(mut addr) => {
+                let port = addr.port().checked_add(1).ok_or_else(|| {
+                    format!("Server port {} leaves no port for PubSub; set pubsub_addr", addr.port())
+                })?;
+                addr.set_port(port);
+                Ok(addr.to_string())
+            }
+            Err(_) => Ok(self.server_addr.clone()),
+        }
+    }
+
+    fn usage() -> String {
+        let mut usage = String::from("Usage: solana-fork-engine [OPTIONS]\n\nOptions:\n");
+        usage.push_str(&format!(
+            "  {:<36} TOML config file (env {}, default ./{})\n",
+            format!("{} <PATH>", CONFIG_FLAG),
+            CONFIG_ENV,
+            DEFAULT_CONFIG_FILE
+        ));
+        for setting in SETTINGS {
+            usage.push_str(&format!(
+                "  {:<36} {} (env {})\n",
+                format!("{} <VALUE>", setting.flag),
+                setting.help,
+                setting.env
+            ));
+        }
+        usage.push_str(&format!("  {:<36} Print this help\n", "-h, --help"));
+        usage
+    }
+
+    /// Apply every setting present in a TOML file
+    fn apply_file(&mut self, path: &Path) -> Result<(), String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        let document: DocumentMut = contents
+            .parse()
+            .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))?;
+
+        for (key, item) in document.iter() {
+            match item {
+                Item::Table(table) => {
+                    for (nested, item) in table.iter() {
+                        self.apply_item(path, &format!("{}.{}", key, nested), item)?;
+                    }
+                }
+                item => self.apply_item(path, key, item)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_item(&mut self, path: &Path, key: &str, item: &Item) -> Result<(), String> {
+        if !SETTINGS.iter().any(|setting| setting.key == key) {
+            return Err(format!("Unknown setting `{}` in {}", key, path.display()));
+        }
+
+        let value = match item.as_value() {
+            Some(Value::String(value)) => value.value().clone(),
+            Some(Value::Integer(value)) => value.value().to_string(),
+            _ => return Err(format!("`{}` in {} must be a string or an integer", key, path.display())),
+        };
+
+        self.set(key, &value).map_err(|e| format!("`{}` in {}: {}", key, path.display(), e))
+    }
+
+    /// Set one setting from its textual value
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "server_addr" => self.server_addr = value.to_string(),
+            "pubsub_addr" => self.pubsub_addr = Some(value.to_string()),
+            "data_dir" => self.data_dir = value.to_string(),
+            "rpc_url" => self.rpc_url = value.to_string(),
+            "rpc.timeout" => {
+                let seconds = parse_number(value)?;
+                if seconds == 0 {
+                    return Err("must be at least 1 second".to_string());
+                }
+                self.rpc.timeout = Duration::from_secs(seconds);
+            }
+            "rpc.max_retries" => self.rpc.max_retries = parse_number(value)?,
+            "rpc.retry_backoff_ms" => self.rpc.retry_backoff = Duration::from_millis(parse_number(value)?),
+            "rpc.record_dir" => self.rpc_record_dir = value.to_string(),
+            "rpc.fixture_root" => self.rpc_fixture_root = value.to_string(),
+            "account_cache.max_entries" => self.account_cache.max_entries = parse_number(value)?,
+            "account_cache.max_age" => {
+                self.account_cache.max_age = parse_limit(value)?.map(Duration::from_secs);
+            }
+            "account_cache.path" => self.account_cache.path = value.to_string(),
+            "fork_lifetime" => self.fork_lifetime = Duration::from_secs(parse_number(value)?),
+            "cleanup_interval" => {
+                let seconds = parse_number(value)?;
+                if seconds == 0 {
+                    return Err("must be at least 1 second".to_string());
+                }
+                self.cleanup_interval = Duration::from_secs(seconds);
+            }
+            "max_forks" => self.max_forks = parse_limit(value)?,
+            "fork_limits.max_snapshots" => self.fork_limits.max_snapshots = parse_limit(value)?,
+            "fork_limits.max_transactions" => self.fork_limits.max_transactions = parse_limit(value)?,
+            _ => return Err(format!("unknown setting `{}`", key)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Split `--flag value` and `--flag=value` arguments into pairs
+fn parse_flags(args: &[String]) -> Result<Vec<(String, String)>, String> {
+    let mut flags = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            return Err(format!("Unexpected argument {}", arg));
+        }
+
+        match arg.split_once('=') {
+            Some((flag, value)) => flags.push((flag.to_string(), value.to_string())),
+            None => {
+                let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+                flags.push((arg.clone(), value.clone()));
+            }
+        }
+    }
+
+    Ok(flags)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid number `{}`", value))
+}
+
+/// Parse a limit where 0 means unlimited
+fn parse_limit<T: std::str::FromStr + Default + PartialEq>(value: &str) -> Result<Option<T>, String> {
+    let limit: T = parse_number(value)?;
+    Ok((limit != T::default()).then_some(limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(args: &[&str], env: &[(&str, &str)]) -> Result<Config, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let env: HashMap<String, String> = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Config::from_sources(&args, |name| env.get(name).cloned())
+    }
+
+    fn config_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("fork-engine-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn flags_beat_env_beat_file_beat_defaults() {
+        let path = config_file(
+            "precedence",
+            "fork_lifetime = 100\nmax_forks = 5\n\n[rpc]\nmax_retries = 9\n",
+        );
+        let config = load(
+            &["--config", &path, "--fork-lifetime", "300"],
+            &[("FORK_LIFETIME", "200"), ("MAX_FORKS", "6")],
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.fork_lifetime, Duration::from_secs(300));
+        assert_eq!(config.max_forks, Some(6));
+        assert_eq!(config.rpc.max_retries, 9);
+        assert_eq!(config.cleanup_interval, Duration::from_secs(DEFAULT_CLEANUP_INTERVAL_SECONDS));
+    }
+
+    #[test]
+    fn zero_limits_are_unlimited() {
+        let config = load(&["--max-forks=0", "--account-cache-max-age", "0"], &[]).unwrap();
+
+        assert_eq!(config.max_forks, None);
+        assert_eq!(config.account_cache.max_age, None);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(load(&["--rpc-timeout", "0"], &[]).is_err());
+        assert!(load(&["--fork-lifetime", "soon"], &[]).is_err());
+        assert!(load(&[], &[("MAX_FORKS", "-1")]).is_err());
+        assert!(load(&["--unknown", "1"], &[]).is_err());
+        assert!(load(&["--max-forks"], &[]).is_err());
+
+        let path = config_file("unknown-key", "forks = 1\n");
+        let result = load(&["--config", &path], &[]);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn pubsub_port_must_fit_after_server_port() {
+        assert!(load(&["--server-addr", "127.0.0.1:65535"], &[]).is_err());
+
+        let config = load(&["--server-addr", "127.0.0.1:65535", "--pubsub-addr", "127.0.0.1:9000"], &[]).unwrap();
+        assert_eq!(config.pubsub_addr().unwrap(), "127.0.0.1:9000");
+
+        let config = load(&["--server-addr", "127.0.0.1:8899"], &[]).unwrap();
+        assert_eq!(config.pubsub_addr().unwrap(), "127.0.0.1:8900");
+    }
+}
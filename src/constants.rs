@@ -3,11 +3,73 @@ use solana_pubkey::Pubkey;
 /// System program ID constant
 pub const SYSTEM_PROGRAM_ID: Pubkey = solana_pubkey::pubkey!("11111111111111111111111111111111");
 
+/// BPF Loader v2 program ID
+pub const BPF_LOADER_ID: Pubkey = solana_pubkey::pubkey!("BPFLoader2111111111111111111111111111111111");
+
+/// BPF Upgradeable Loader (v3) program ID
+pub const BPF_LOADER_UPGRADEABLE_ID: Pubkey =
+    solana_pubkey::pubkey!("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/// Sysvars copied from mainnet into mainnet-synced forks
+pub const MAINNET_SYSVARS: [Pubkey; 8] = [
+    solana_sdk::sysvar::clock::ID,
+    solana_sdk::sysvar::rent::ID,
+    solana_sdk::sysvar::epoch_schedule::ID,
+    solana_sdk::sysvar::epoch_rewards::ID,
+    solana_sdk::sysvar::slot_hashes::ID,
+    solana_sdk_ids::sysvar::stake_history::ID,
+    solana_sdk::sysvar::last_restart_slot::ID,
+    #[allow(deprecated)]
+    solana_sdk::sysvar::recent_blockhashes::ID,
+];
+
+/// Largest account data size the runtime allows
+pub const MAX_ACCOUNT_DATA_LENGTH: usize = 10 * 1024 * 1024;
+
 /// Lamports per SOL
 pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 
+/// Target slot duration, used to move the clock forward when warping
+pub const MS_PER_SLOT: u64 = 400;
+
 /// Default server address
 pub const DEFAULT_SERVER_ADDR: &str = "0.0.0.0:8899";
 
+/// Default upstream RPC endpoint
+pub const DEFAULT_RPC_URL: &str = "https://api.mainnet-beta.solana.com";
+
+/// Default time allowed for a single upstream RPC request, in seconds
+pub const DEFAULT_RPC_TIMEOUT_SECONDS: u64 = 30;
+
+/// Default number of times a failed upstream RPC request is retried
+pub const DEFAULT_RPC_MAX_RETRIES: u32 = 3;
+
+/// Default delay before the first upstream RPC retry, in milliseconds
+pub const DEFAULT_RPC_RETRY_BACKOFF_MS: u64 = 250;
+
+/// Default number of upstream accounts kept in the shared account cache
+pub const DEFAULT_ACCOUNT_CACHE_MAX_ENTRIES: usize = 10_000;
+
+/// Default age after which a cached upstream account is fetched again, in seconds
+pub const DEFAULT_ACCOUNT_CACHE_MAX_AGE_SECONDS: u64 = 60;
+
+/// Default fork lifetime in seconds (15 minutes)
+pub const DEFAULT_FORK_LIFETIME_SECONDS: u64 = 15 * 60;
+
+/// Default interval between expired fork cleanups, in seconds
+pub const DEFAULT_CLEANUP_INTERVAL_SECONDS: u64 = 60;
+
+/// Config file read from the working directory when none is given
+pub const DEFAULT_CONFIG_FILE: &str = "fork-engine.toml";
+
+/// Capacity of each fork's PubSub event channel; slower subscribers skip older events
+pub const FORK_EVENT_CAPACITY: usize = 1024;
+
+/// Transactions kept in each fork's history; older ones are forgotten
+pub const MAX_TRANSACTION_HISTORY: usize = 10_000;
+
+/// Default directory forks are persisted to
+pub const DEFAULT_DATA_DIR: &str = "fork-data";
+
 /// API version
 pub const API_VERSION: &str = "v1";
\ No newline at end of file
@@ -1,3 +1,4 @@
+pub mod config;
 pub mod constants;
 pub mod models;
 pub mod routes;
@@ -6,6 +7,7 @@ pub mod utils;
 pub mod services;
 
 // Re-export commonly used items for convenience
+pub use config::{AccountCacheSettings, Config, ForkLimits, RpcSettings};
 pub use constants::*;
 
 pub use models::*;
@@ -3,32 +3,94 @@ use axum::{
     Router,
 };
 use solana_fork_engine::{
-    constants::DEFAULT_SERVER_ADDR,
-    create_shared_fork_manager,
     routes,
     state::AppState,
+    AccountCache,
+    Config,
+    FixtureRecorder,
+    ForkManager,
+    ForkStore,
 };
 
-use std::time::Duration;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() {
+    let config = match Config::load() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    };
+
     println!("🚀 Starting Solana Fork Engine v0.4.0");
     println!();
     
-    let fork_manager = create_shared_fork_manager();
-    let state = AppState::new(fork_manager.clone()); 
+    let server_addr = config.server_addr.clone();
+
+    // Load the shared upstream account cache before restored forks start using it
+    let account_cache = Arc::new(AccountCache::new(config.account_cache.clone()));
+    match account_cache.load() {
+        Ok(loaded) if loaded > 0 => {
+            println!("🗄️  Loaded {} cached account(s) from {}", loaded, config.account_cache.path)
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Warning: Failed to load account cache: {}", e),
+    }
+
+    let mut fork_manager = ForkManager::with_config(&config);
+    fork_manager.enable_account_cache(account_cache.clone());
+
+    // Record everything fetched from upstream as a fixture directory, if enabled
+    let mut recorder = None;
+    if !config.rpc_record_dir.is_empty() {
+        match FixtureRecorder::new(&config.rpc_record_dir) {
+            Ok(fixture_recorder) => {
+                let fixture_recorder = Arc::new(fixture_recorder);
+                fork_manager.enable_recording(fixture_recorder.clone());
+                recorder = Some(fixture_recorder);
+                println!("📼 Recording upstream fetches to {}", config.rpc_record_dir);
+            }
+            Err(e) => eprintln!("Warning: Upstream recording disabled: {}", e),
+        }
+    }
+
+    // Restore persisted forks (an empty data directory disables persistence)
+    let data_dir = &config.data_dir;
+    if !data_dir.is_empty() {
+        match ForkStore::new(data_dir) {
+            Ok(store) => {
+                fork_manager.enable_persistence(store);
+                match fork_manager.restore_forks() {
+                    Ok(restored) => println!("💾 Restored {} fork(s) from {}", restored, data_dir),
+                    Err(e) => eprintln!("Warning: Failed to restore forks: {}", e),
+                }
+            }
+            Err(e) => eprintln!("Warning: Fork persistence disabled: {}", e),
+        }
+    }
+
+    let fork_manager = Arc::new(fork_manager);
+    let state = AppState::new(fork_manager.clone(), account_cache.clone(), recorder, config.clone());
     
     let cleanup_fork_manager = fork_manager.clone();
+    let cleanup_account_cache = account_cache.clone();
+    let cleanup_interval = config.cleanup_interval;
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(60)); 
+        let mut interval = tokio::time::interval(cleanup_interval); 
         loop {
             interval.tick().await;
-            if let Ok(mut manager) = cleanup_fork_manager.lock() {
-                let cleaned = manager.cleanup_expired_forks();
-                if cleaned > 0 {
-                    println!("🧹 Cleaned up {} expired fork(s)", cleaned);
-                }
+            let cleaned = cleanup_fork_manager.cleanup_expired_forks();
+            if cleaned > 0 {
+                println!("🧹 Cleaned up {} expired fork(s)", cleaned);
+            }
+            if let Err(e) = cleanup_fork_manager.persist_forks().await {
+                eprintln!("Warning: Failed to persist forks: {}", e);
+            }
+            cleanup_account_cache.remove_expired();
+            if let Err(e) = cleanup_account_cache.persist() {
+                eprintln!("Warning: Failed to persist account cache: {}", e);
             }
         }
     });
@@ -43,6 +105,10 @@ async fn main() {
         // Fork management
         .route("/api/v1/forks", get(routes::list_forks))
         .route("/api/v1/forks", post(routes::create_fork))
+        .route("/api/v1/forks/cleanup", post(routes::cleanup_forks))
+        .route("/api/v1/forks/{fork_id}/clone", post(routes::clone_fork))
+        .route("/api/v1/forks/import", post(routes::import_fork))
+        .route("/api/v1/fork/export", get(routes::export_fork))
         
         // Mainnet forking routes
         .route("/api/v1/forks/mainnet", post(routes::create_mainnet_fork))
@@ -54,14 +120,34 @@ async fn main() {
         .route("/api/v1/fork/balance/set", post(routes::set_balance))
         .route("/api/v1/fork/balance/get", post(routes::get_balance))
         .route("/api/v1/fork/account", post(routes::get_account))
+        .route("/api/v1/fork/account/set", post(routes::set_account))
         .route("/api/v1/fork/airdrop", post(routes::airdrop))
         
+        // Snapshot operations
+        .route("/api/v1/fork/snapshot", post(routes::create_snapshot))
+        .route("/api/v1/fork/revert", post(routes::revert_snapshot))
+        
+        // Time travel
+        .route("/api/v1/fork/clock/warp", post(routes::warp_to_slot))
+        .route("/api/v1/fork/clock/advance", post(routes::advance_slots))
+        .route("/api/v1/fork/clock/epoch", post(routes::warp_to_epoch))
+        .route("/api/v1/fork/clock/timestamp", post(routes::set_unix_timestamp))
+        
         // Transaction operations (require user_id query param)
         .route(
             "/api/v1/fork/transaction/send",
             post(routes::send_transaction),
         )
         .route("/api/v1/fork/transfer", post(routes::transfer_sol))
+        .route("/api/v1/fork/transactions", get(routes::list_transactions))
+        
+        // Impersonation
+        .route("/api/v1/fork/impersonate", post(routes::impersonate_account))
+        .route("/api/v1/fork/impersonate/stop", post(routes::stop_impersonating))
+        .route(
+            "/api/v1/fork/transaction/impersonated",
+            post(routes::send_impersonated_transaction),
+        )
         
         // Token operations
         .route("/api/v1/token/create-mint", post(routes::create_token_mint))
@@ -69,20 +155,71 @@ async fn main() {
         .route("/api/v1/token/mint", post(routes::mint_tokens))
         .route("/api/v1/token/transfer", post(routes::transfer_tokens))
         .route("/api/v1/token/balance", post(routes::get_token_balance))
-        .route("/rpc", post(routes::handle_rpc))
+        .route("/api/v1/token/balance/set", post(routes::set_token_balance))
         
+        // Program operations
+        .route("/api/v1/program/deploy", post(routes::deploy_program))
+        .route("/api/v1/program/upgrade", post(routes::upgrade_program))
+        .route("/api/v1/program/invoke", post(routes::invoke_program))
+        .route("/api/v1/program/load", post(routes::load_program))
+        .route("/api/v1/program/info", post(routes::get_program_info))
         
+        // Admin
+        .route(
+            "/api/v1/admin/account-cache",
+            get(routes::get_account_cache_stats).delete(routes::clear_account_cache),
+        )
+
+        .route("/rpc", post(routes::handle_rpc).get(routes::handle_pubsub))
+        .route("/rpc/fork/{fork_id}", post(routes::handle_fork_rpc).get(routes::handle_fork_pubsub))
+        .route("/rpc/user/{user_id}", post(routes::handle_user_rpc).get(routes::handle_user_pubsub))
+        
+        
+        .with_state(state.clone());
+
+    // Solana clients look for PubSub on the RPC port + 1, so serve it there as well
+    let pubsub_app = Router::new()
+        .route("/rpc", get(routes::handle_pubsub))
+        .route("/rpc/fork/{fork_id}", get(routes::handle_fork_pubsub))
+        .route("/rpc/user/{user_id}", get(routes::handle_user_pubsub))
         .with_state(state);
+
+    let pubsub_addr = config
+        .pubsub_addr()
+        .expect("PubSub address is validated when the config is loaded");
+    match tokio::net::TcpListener::bind(&pubsub_addr).await {
+        Ok(listener) => {
+            println!("🔔 PubSub listening on ws://{}", pubsub_addr);
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(listener, pubsub_app).await {
+                    eprintln!("Warning: PubSub server stopped: {}", e);
+                }
+            });
+        }
+        Err(e) => eprintln!("Warning: Failed to bind PubSub to {}: {}", pubsub_addr, e),
+    }
     
     // Print available endpoints
-    println!("🌐 Server listening on http://{}", DEFAULT_SERVER_ADDR);
+    println!("🌐 Server listening on http://{}", server_addr);
     println!();
     
     // Start the server
-    let listener = tokio::net::TcpListener::bind(DEFAULT_SERVER_ADDR)
+    let listener = tokio::net::TcpListener::bind(&server_addr)
         .await
         .expect("Failed to bind to address");
     axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
         .await
         .expect("Server failed to start");
-}
\ No newline at end of file
+
+    // Persist forks one last time before exiting
+    match fork_manager.persist_forks().await {
+        Ok(persisted) => println!("💾 Persisted {} fork(s)", persisted),
+        Err(e) => eprintln!("Warning: Failed to persist forks: {}", e),
+    }
+    if let Err(e) = account_cache.persist() {
+        eprintln!("Warning: Failed to persist account cache: {}", e);
+    }
+}
@@ -0,0 +1,22 @@
+use solana_pubkey::Pubkey;
+
+/// Changes written to an account by the `set_account` cheat.
+/// `None` keeps the account's current value.
+#[derive(Debug, Default)]
+pub struct AccountUpdate {
+    pub lamports: Option<u64>,
+    pub owner: Option<Pubkey>,
+    pub executable: Option<bool>,
+    pub rent_epoch: Option<u64>,
+    /// Replaces the whole account data
+    pub data: Option<Vec<u8>>,
+    /// Written in order after `data`; the data grows as needed
+    pub patches: Vec<DataPatch>,
+}
+
+/// Bytes to write at an offset of an account's data
+#[derive(Debug)]
+pub struct DataPatch {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+}
@@ -0,0 +1,140 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use serde::Serialize;
+use solana_account::Account;
+use solana_pubkey::Pubkey;
+
+use crate::utils::unpack_token_account;
+
+/// How one account changed across a transaction.
+/// `None` on either side means the account did not exist at that point.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountDiff {
+    pub address: String,
+    pub lamports_before: u64,
+    pub lamports_after: u64,
+    pub lamports_delta: i128,
+    pub owner_before: Option<String>,
+    pub owner_after: Option<String>,
+    pub data_len_before: usize,
+    pub data_len_after: usize,
+    /// Byte ranges of the account data that differ
+    pub data_changes: Vec<DataChange>,
+    /// Decoded changes if the account is an SPL token account on either side
+    pub token: Option<TokenAccountDiff>,
+}
+
+/// A contiguous range of changed account data, base64 encoded.
+/// When the length changed, the grown or truncated tail is its own range.
+#[derive(Debug, Clone, Serialize)]
+pub struct DataChange {
+    pub offset: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// Changes to the decoded fields of an SPL token account
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenAccountDiff {
+    pub mint: String,
+    pub owner: String,
+    pub amount_before: u64,
+    pub amount_after: u64,
+    pub amount_delta: i128,
+    pub delegate_before: Option<String>,
+    pub delegate_after: Option<String>,
+    pub delegated_amount_before: u64,
+    pub delegated_amount_after: u64,
+    pub state_before: Option<String>,
+    pub state_after: Option<String>,
+}
+
+/// Diff every account in `addresses` that changed between `pre` and `post`.
+/// All three slices are indexed the same way.
+pub fn diff_accounts(
+    addresses: &[Pubkey],
+    pre: &[Option<Account>],
+    post: &[Option<Account>],
+) -> Vec<AccountDiff> {
+    addresses
+        .iter()
+        .zip(pre.iter().zip(post))
+        .filter(|(_, (before, after))| before != after)
+        .map(|(address, (before, after))| diff_account(address, before.as_ref(), after.as_ref()))
+        .collect()
+}
+
+fn diff_account(address: &Pubkey, before: Option<&Account>, after: Option<&Account>) -> AccountDiff {
+    let lamports_before = before.map_or(0, |a| a.lamports);
+    let lamports_after = after.map_or(0, |a| a.lamports);
+    let data_before = before.map_or(&[][..], |a| a.data.as_slice());
+    let data_after = after.map_or(&[][..], |a| a.data.as_slice());
+
+    AccountDiff {
+        address: address.to_string(),
+        lamports_before,
+        lamports_after,
+        lamports_delta: lamports_after as i128 - lamports_before as i128,
+        owner_before: before.map(|a| a.owner.to_string()),
+        owner_after: after.map(|a| a.owner.to_string()),
+        data_len_before: data_before.len(),
+        data_len_after: data_after.len(),
+        data_changes: diff_data(data_before, data_after),
+        token: diff_token_account(before, after),
+    }
+}
+
+fn diff_data(before: &[u8], after: &[u8]) -> Vec<DataChange> {
+    let common = before.len().min(after.len());
+    let mut changes = Vec::new();
+
+    let mut offset = 0;
+    while offset < common {
+        if before[offset] == after[offset] {
+            offset += 1;
+            continue;
+        }
+
+        let start = offset;
+        while offset < common && before[offset] != after[offset] {
+            offset += 1;
+        }
+        changes.push(DataChange {
+            offset: start,
+            before: BASE64.encode(&before[start..offset]),
+            after: BASE64.encode(&after[start..offset]),
+        });
+    }
+
+    if before.len() != after.len() {
+        changes.push(DataChange {
+            offset: common,
+            before: BASE64.encode(&before[common..]),
+            after: BASE64.encode(&after[common..]),
+        });
+    }
+
+    changes
+}
+
+fn diff_token_account(before: Option<&Account>, after: Option<&Account>) -> Option<TokenAccountDiff> {
+    let before = before.and_then(unpack_token_account);
+    let after = after.and_then(unpack_token_account);
+    let current = after.as_ref().or(before.as_ref())?;
+
+    let amount_before = before.as_ref().map_or(0, |t| t.amount);
+    let amount_after = after.as_ref().map_or(0, |t| t.amount);
+
+    Some(TokenAccountDiff {
+        mint: current.mint.to_string(),
+        owner: current.owner.to_string(),
+        amount_before,
+        amount_after,
+        amount_delta: amount_after as i128 - amount_before as i128,
+        delegate_before: before.as_ref().and_then(|t| Option::from(t.delegate)).map(|d: Pubkey| d.to_string()),
+        delegate_after: after.as_ref().and_then(|t| Option::from(t.delegate)).map(|d: Pubkey| d.to_string()),
+        delegated_amount_before: before.as_ref().map_or(0, |t| t.delegated_amount),
+        delegated_amount_after: after.as_ref().map_or(0, |t| t.delegated_amount),
+        state_before: before.as_ref().map(|t| format!("{:?}", t.state).to_lowercase()),
+        state_after: after.as_ref().map(|t| format!("{:?}", t.state).to_lowercase()),
+    })
+}
@@ -0,0 +1,24 @@
+use solana_account::Account;
+use solana_pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+/// State change on a fork, broadcast to PubSub subscribers
+#[derive(Debug, Clone)]
+pub enum ForkEvent {
+    /// An account was written by a transaction or a cheat endpoint
+    Account {
+        pubkey: Pubkey,
+        account: Account,
+        slot: u64,
+    },
+    /// A transaction was processed, successfully or not
+    Transaction {
+        signature: String,
+        err: Option<TransactionError>,
+        logs: Vec<String>,
+        account_keys: Vec<Pubkey>,
+        slot: u64,
+    },
+    /// The fork advanced to a new slot
+    Slot { slot: u64 },
+}
@@ -1,9 +1,52 @@
+use litesvm::types::{FailedTransactionMetadata, SimulatedTransactionInfo, TransactionMetadata};
 use litesvm::LiteSVM;
+use solana_address_lookup_table_interface::{program as address_lookup_table, state::AddressLookupTable};
+use solana_account::Account;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_fee::FeeFeatures;
+use solana_fee_structure::FeeStructure;
+use solana_message::v0::{LoadedAddresses, LoadedMessage};
+use solana_message::{LegacyMessage, SanitizedMessage, VersionedMessage};
 use solana_pubkey::Pubkey;
-use solana_transaction::Transaction;
+use solana_sdk::clock::Clock;
+use solana_sdk::epoch_schedule::EpochSchedule;
+use solana_sdk::hash::{hash, Hash};
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use solana_sdk::slot_hashes::SlotHashes;
+use solana_transaction::versioned::VersionedTransaction;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::solana_program::program_option::COption;
+use spl_token::solana_program::program_pack::Pack;
+use spl_token_2022_interface::extension::StateWithExtensionsMut;
+use spl_token_2022_interface::state::{Account as TokenAccount, AccountState as TokenAccountState, Mint};
+use std::collections::HashSet;
+use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
 
+use crate::constants::{
+    BPF_LOADER_UPGRADEABLE_ID, FORK_EVENT_CAPACITY, MAINNET_SYSVARS,
+    MAX_ACCOUNT_DATA_LENGTH, MS_PER_SLOT,
+};
+use crate::models::account_update::AccountUpdate;
+use crate::models::diff::{diff_accounts, AccountDiff};
+use crate::models::event::ForkEvent;
+use crate::models::history::{TokenBalance, TransactionHistory, TransactionRecord};
+use crate::models::fork_state::{AccountState, ForkState, FORK_STATE_VERSION};
 use crate::models::responses::{AccountInfo, ForkInfo, TransactionResult};
+use crate::models::token::SetTokenBalanceResponse;
+use crate::services::MainnetClient;
+use crate::utils::{
+    get_upgradeable_loader_state, is_native_mint, pack_token_account, token_extension_types, unpack_mint,
+    unpack_token_account,
+};
+
+/// Outcome of executing a transaction on a fork
+pub type ExecutionResult = Result<TransactionMetadata, FailedTransactionMetadata>;
+
+/// Outcome of simulating a transaction against a fork
+pub type SimulationResult = Result<SimulatedTransactionInfo, FailedTransactionMetadata>;
 
 /// Represents a Solana blockchain fork with metadata
 pub struct Fork {
@@ -14,6 +57,34 @@ pub struct Fork {
     pub mainnet_slot: u64,        
     pub mainnet_blockhash: String, 
     pub transaction_count: u64,
+    /// Blockhash handed to clients. Mainnet forks start with mainnet's, which
+    /// the VM accepts alongside its own until the next warp.
+    blockhash: Hash,
+    /// Upstream used to lazily fetch accounts missing from the fork
+    pub upstream: Option<MainnetClient>,
+    /// Accounts already requested from upstream, so they are never fetched twice
+    fetched_accounts: HashSet<Pubkey>,
+    /// Accounts treated as valid signers without checking their signatures
+    impersonated: HashSet<Pubkey>,
+    /// Executed transactions, served by getTransaction and friends
+    pub history: TransactionHistory,
+    /// State changes, fanned out to PubSub subscribers
+    events: broadcast::Sender<ForkEvent>,
+    /// Transactions the fork executes before refusing more; `None` is unlimited
+    pub max_transactions: Option<u64>,
+}
+
+/// Point-in-time copy of a fork's state that the fork can be reverted to
+pub struct ForkSnapshot {
+    pub id: String,
+    pub created_at: u64,
+    pub slot: u64,
+    svm: LiteSVM,
+    transaction_count: u64,
+    mainnet_blockhash: String,
+    blockhash: Hash,
+    fetched_accounts: HashSet<Pubkey>,
+    history: TransactionHistory,
 }
 
 impl Fork {
@@ -28,6 +99,7 @@ impl Fork {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
+        let blockhash = Hash::from_str(&mainnet_blockhash).unwrap_or(svm.latest_blockhash());
 
         Self {
             id,
@@ -37,10 +109,18 @@ impl Fork {
             mainnet_slot,                 
             mainnet_blockhash,            
             transaction_count: 0,
+            blockhash,
+            upstream: None,
+            fetched_accounts: HashSet::new(),
+            impersonated: HashSet::new(),
+            history: TransactionHistory::default(),
+            events: broadcast::channel(FORK_EVENT_CAPACITY).0,
+            max_transactions: None,
         }
     }
     pub fn new(id: String) -> Self {
         let svm = LiteSVM::new();
+        let blockhash = svm.latest_blockhash();
         let created_at = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -54,6 +134,13 @@ impl Fork {
             mainnet_slot: 0,
             mainnet_blockhash: String::new(),
             transaction_count: 0,
+            blockhash,
+            upstream: None,
+            fetched_accounts: HashSet::new(),
+            impersonated: HashSet::new(),
+            history: TransactionHistory::default(),
+            events: broadcast::channel(FORK_EVENT_CAPACITY).0,
+            max_transactions: None,
         }
     }
 
@@ -73,11 +160,582 @@ impl Fork {
         }
     }
 
-      pub fn add_balance(&mut self, address: &Pubkey, lamports: u64) -> Result<(), String> {
+    /// Deep-copy the fork's accounts, programs, sysvars and chain metadata
+    /// into a new, independent fork with the given ID
+    pub fn clone_as(&self, id: String) -> Fork {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Self {
+            id,
+            svm: self.svm.clone(),
+            created_at,
+            slot: self.slot,
+            mainnet_slot: self.mainnet_slot,
+            mainnet_blockhash: self.mainnet_blockhash.clone(),
+            transaction_count: self.transaction_count,
+            blockhash: self.blockhash,
+            upstream: self.upstream.clone(),
+            fetched_accounts: self.fetched_accounts.clone(),
+            impersonated: self.impersonated.clone(),
+            history: self.history.clone(),
+            events: broadcast::channel(FORK_EVENT_CAPACITY).0,
+            max_transactions: self.max_transactions,
+        }
+    }
+
+    /// Serialize the fork for persistence or export. Accounts identical to those
+    /// of a fresh LiteSVM (builtins, default programs, untouched sysvars) are skipped.
+    pub fn to_state(&self, user_id: Option<String>) -> ForkState {
+        let baseline = LiteSVM::new();
+
+        let mut accounts: Vec<AccountState> = self
+            .svm
+            .accounts_db()
+            .inner
+            .iter()
+            .filter(|(address, account)| baseline.accounts_db().inner.get(address) != Some(account))
+            .map(|(address, account)| AccountState::from_account(address, &account.clone().into()))
+            .collect();
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+
+        let mut fetched_accounts: Vec<String> =
+            self.fetched_accounts.iter().map(|a| a.to_string()).collect();
+        fetched_accounts.sort();
+
+        let mut impersonated: Vec<String> =
+            self.impersonated.iter().map(|a| a.to_string()).collect();
+        impersonated.sort();
+
+        ForkState {
+            version: FORK_STATE_VERSION,
+            fork_id: self.id.clone(),
+            user_id,
+            created_at: self.created_at,
+            slot: self.slot,
+            mainnet_slot: self.mainnet_slot,
+            mainnet_blockhash: self.mainnet_blockhash.clone(),
+            transaction_count: self.transaction_count,
+            blockhash: Some(self.blockhash.to_string()),
+            rpc_endpoint: self.upstream.as_ref().map(|client| client.endpoint()),
+            fetched_accounts,
+            impersonated,
+            accounts,
+        }
+    }
+
+    /// Rebuild a fork from its serialized state. `upstream` serves lazy
+    /// loading; the caller decides whether to trust the state's `rpc_endpoint`.
+    pub fn from_state(state: &ForkState, upstream: Option<MainnetClient>) -> Result<Fork, String> {
+        if state.version != FORK_STATE_VERSION {
+            return Err(format!("Unsupported fork state version: {}", state.version));
+        }
+
+        let mut accounts = state
+            .accounts
+            .iter()
+            .map(|account| account.to_account())
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // Install ProgramData accounts before the programs that point at them
+        accounts.sort_by_key(|(_, account)| {
+            !matches!(
+                get_upgradeable_loader_state(account),
+                Some(UpgradeableLoaderState::ProgramData { .. })
+            )
+        });
+
+        let mut svm = LiteSVM::new();
+        for (address, account) in accounts {
+            svm.set_account(address, account)
+                .map_err(|e| format!("Failed to restore account {}: {:?}", address, e))?;
+        }
+
+        let fetched_accounts = state
+            .fetched_accounts
+            .iter()
+            .map(|address| {
+                Pubkey::from_str(address).map_err(|e| format!("Invalid address {}: {}", address, e))
+            })
+            .collect::<Result<HashSet<_>, String>>()?;
+
+        let impersonated = state
+            .impersonated
+            .iter()
+            .map(|address| {
+                Pubkey::from_str(address).map_err(|e| format!("Invalid address {}: {}", address, e))
+            })
+            .collect::<Result<HashSet<_>, String>>()?;
+
+        // The VM's own blockhash is not persisted, so keep accepting the one clients have
+        let blockhash = match &state.blockhash {
+            Some(blockhash) => Hash::from_str(blockhash)
+                .map_err(|e| format!("Invalid blockhash {}: {}", blockhash, e))?,
+            None => svm.latest_blockhash(),
+        };
+
+        Ok(Self {
+            id: state.fork_id.clone(),
+            svm,
+            created_at: state.created_at,
+            slot: state.slot,
+            mainnet_slot: state.mainnet_slot,
+            mainnet_blockhash: state.mainnet_blockhash.clone(),
+            transaction_count: state.transaction_count,
+            blockhash,
+            upstream,
+            fetched_accounts,
+            impersonated,
+            history: TransactionHistory::default(),
+            events: broadcast::channel(FORK_EVENT_CAPACITY).0,
+            max_transactions: None,
+        })
+    }
+
+    /// Capture the full account state and chain metadata of the fork
+    pub fn snapshot(&self, id: String) -> ForkSnapshot {
+        ForkSnapshot {
+            id,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            slot: self.slot,
+            svm: self.svm.clone(),
+            transaction_count: self.transaction_count,
+            mainnet_blockhash: self.mainnet_blockhash.clone(),
+            blockhash: self.blockhash,
+            fetched_accounts: self.fetched_accounts.clone(),
+            history: self.history.clone(),
+        }
+    }
+
+    /// Restore the fork to the exact state captured in `snapshot`, notifying
+    /// subscribers of every account the revert changed
+    pub fn revert(&mut self, snapshot: &ForkSnapshot) {
+        let changed: Vec<Pubkey> = if self.events.receiver_count() > 0 {
+            let current = &self.svm.accounts_db().inner;
+            let restored = &snapshot.svm.accounts_db().inner;
+            current
+                .keys()
+                .chain(restored.keys().filter(|address| !current.contains_key(*address)))
+                .filter(|address| current.get(*address) != restored.get(*address))
+                .copied()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        self.svm = snapshot.svm.clone();
+        self.slot = snapshot.slot;
+        self.transaction_count = snapshot.transaction_count;
+        self.mainnet_blockhash = snapshot.mainnet_blockhash.clone();
+        self.blockhash = snapshot.blockhash;
+        self.fetched_accounts = snapshot.fetched_accounts.clone();
+        self.history = snapshot.history.clone();
+
+        self.notify_accounts(&changed);
+    }
+
+    /// Receive every state change of this fork from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ForkEvent> {
+        self.events.subscribe()
+    }
+
+    /// Tell subscribers about the current state of the given accounts.
+    /// Must be called by anything that writes accounts outside `send_transaction`.
+    pub fn notify_accounts(&self, addresses: &[Pubkey]) {
+        if self.events.receiver_count() == 0 {
+            return;
+        }
+
+        for address in addresses {
+            // Closed accounts are reported as empty, like on a real cluster
+            let account = self.svm.get_account(address).unwrap_or_default();
+            let _ = self.events.send(ForkEvent::Account {
+                pubkey: *address,
+                account,
+                slot: self.slot,
+            });
+        }
+    }
+
+    /// SPL token balances of the token accounts among `account_keys`
+    fn token_balances(&self, account_keys: &[Pubkey]) -> Vec<TokenBalance> {
+        account_keys
+            .iter()
+            .enumerate()
+            .filter_map(|(account_index, address)| {
+                let account = self.svm.get_account(address)?;
+                let token_account = unpack_token_account(&account)?;
+                let decimals = self
+                    .svm
+                    .get_account(&token_account.mint)
+                    .and_then(|mint| unpack_mint(&mint))
+                    .map_or(0, |mint| mint.decimals);
+
+                Some(TokenBalance {
+                    account_index,
+                    mint: token_account.mint,
+                    owner: token_account.owner,
+                    program_id: account.owner,
+                    amount: token_account.amount,
+                    decimals,
+                })
+            })
+            .collect()
+    }
+
+    /// Enable lazy loading: `load_accounts` fetches any account missing from
+    /// the fork from `client`, pinned to `mainnet_slot`
+    pub fn enable_lazy_loading(&mut self, client: MainnetClient) {
+        self.upstream = Some(client);
+    }
+
+    /// Whether missing accounts are fetched from upstream on demand
+    pub fn is_lazy(&self) -> bool {
+        self.upstream.is_some()
+    }
+
+    /// Accounts among `addresses` that lazy loading still has to fetch from upstream
+    pub fn missing_accounts(&self, addresses: &[Pubkey]) -> Vec<Pubkey> {
+        if self.upstream.is_none() {
+            return Vec::new();
+        }
+
+        let mut missing: Vec<Pubkey> = Vec::new();
+        for address in addresses {
+            if !self.fetched_accounts.contains(address)
+                && !missing.contains(address)
+                && self.svm.get_account(address).is_none()
+            {
+                missing.push(*address);
+            }
+        }
+        missing
+    }
+
+    /// Install accounts fetched from upstream for `missing_accounts`. An
+    /// account written or loaded by another request in the meantime is kept.
+    pub fn install_fetched_accounts(&mut self, fetched: Vec<(Pubkey, Option<Account>)>) {
+        let mut accounts: Vec<(Pubkey, Account)> = Vec::new();
+        for (address, account) in fetched {
+            if !self.fetched_accounts.insert(address) || self.svm.get_account(&address).is_some() {
+                continue;
+            }
+            if let Some(account) = account {
+                accounts.push((address, account));
+            }
+        }
+
+        // Install ProgramData accounts before the programs that point at them
+        accounts.sort_by_key(|(_, account)| {
+            !matches!(
+                get_upgradeable_loader_state(account),
+                Some(UpgradeableLoaderState::ProgramData { .. })
+            )
+        });
+
+        for (address, account) in accounts {
+            if let Err(e) = self.svm.set_account(address, account) {
+                eprintln!("Warning: Failed to lazy load account {}: {:?}", address, e);
+            }
+        }
+    }
+
+    /// Resolve the addresses a v0 message loads from address lookup tables,
+    /// as (writable, readonly). A lookup that cannot be resolved yields no
+    /// addresses and is left for the runtime to reject.
+    pub fn lookup_table_addresses(&self, message: &VersionedMessage) -> (Vec<Pubkey>, Vec<Pubkey>) {
+        let lookups = match message.address_table_lookups() {
+            Some(lookups) if !lookups.is_empty() => lookups,
+            _ => return (Vec::new(), Vec::new()),
+        };
+
+        let current_slot = self.svm.get_sysvar::<Clock>().slot;
+        let slot_hashes = self.svm.get_sysvar::<SlotHashes>();
+
+        let mut writable = Vec::new();
+        let mut readonly = Vec::new();
+        for lookup in lookups {
+            let Some(table_account) = self.svm.get_account(&lookup.account_key) else {
+                return (Vec::new(), Vec::new());
+            };
+            if table_account.owner != address_lookup_table::id() {
+                return (Vec::new(), Vec::new());
+            }
+            let Ok(table) = AddressLookupTable::deserialize(&table_account.data) else {
+                return (Vec::new(), Vec::new());
+            };
+
+            match (
+                table.lookup(current_slot, &lookup.writable_indexes, &slot_hashes),
+                table.lookup(current_slot, &lookup.readonly_indexes, &slot_hashes),
+            ) {
+                (Ok(w), Ok(r)) => {
+                    writable.extend(w);
+                    readonly.extend(r);
+                }
+                _ => return (Vec::new(), Vec::new()),
+            }
+        }
+
+        (writable, readonly)
+    }
+
+    /// Install an upgradeable (BPF Loader v3) program together with its ProgramData account.
+    /// The ProgramData account is stored as-is, so the upgrade authority is preserved.
+    pub fn add_upgradeable_program(
+        &mut self,
+        program_id: Pubkey,
+        program_account: Account,
+        programdata_address: Pubkey,
+        programdata_account: Account,
+    ) -> Result<(), String> {
+        // The ELF is only loaded once the program account is set, so keep the
+        // previous ProgramData to put back if the new one fails to load
+        let previous_programdata = self.svm.get_account(&programdata_address);
+
+        self.svm
+            .set_account(programdata_address, programdata_account)
+            .map_err(|e| format!("Failed to set program data account: {:?}", e))?;
+
+        if let Err(e) = self.svm.set_account(program_id, program_account) {
+            let _ = self
+                .svm
+                .set_account(programdata_address, previous_programdata.unwrap_or_default());
+            return Err(format!("Failed to set program account: {:?}", e));
+        }
+
+        self.fetched_accounts.insert(programdata_address);
+        self.fetched_accounts.insert(program_id);
+
+        self.notify_accounts(&[programdata_address, program_id]);
+
+        Ok(())
+    }
+
+    /// Get an account. On a lazy loading fork, load it with `load_accounts` first.
+    pub fn get_account(&self, address: &Pubkey) -> Option<Account> {
+        self.svm.get_account(address)
+    }
+
+    /// Write an account's lamports, owner, flags or data directly, bypassing
+    /// the runtime. Accounts that do not exist yet are created rent-exempt
+    /// unless `lamports` is given.
+    pub fn set_account(&mut self, address: Pubkey, update: AccountUpdate) -> Result<Account, String> {
+        let existing = self.get_account(&address);
+        let is_new = existing.is_none();
+        let mut account = existing.unwrap_or_default();
+
+        if let Some(data) = update.data {
+            account.data = data;
+        }
+        for patch in update.patches {
+            let end = patch
+                .offset
+                .checked_add(patch.bytes.len())
+                .filter(|end| *end <= MAX_ACCOUNT_DATA_LENGTH)
+                .ok_or_else(|| format!("Patch at offset {} exceeds the maximum account size", patch.offset))?;
+            if account.data.len() < end {
+                account.data.resize(end, 0);
+            }
+            account.data[patch.offset..end].copy_from_slice(&patch.bytes);
+        }
+        if account.data.len() > MAX_ACCOUNT_DATA_LENGTH {
+            return Err(format!(
+                "Account data of {} bytes exceeds the maximum of {}",
+                account.data.len(),
+                MAX_ACCOUNT_DATA_LENGTH
+            ));
+        }
+
+        account.lamports = match update.lamports {
+            Some(lamports) => lamports,
+            None if is_new => self.svm.minimum_balance_for_rent_exemption(account.data.len()),
+            None => account.lamports,
+        };
+        if let Some(owner) = update.owner {
+            account.owner = owner;
+        }
+        if let Some(executable) = update.executable {
+            account.executable = executable;
+        }
+        if let Some(rent_epoch) = update.rent_epoch {
+            account.rent_epoch = rent_epoch;
+        }
+
+        self.svm
+            .set_account(address, account.clone())
+            .map_err(|e| format!("Failed to set account: {:?}", e))?;
+
+        // Never let lazy loading replace a fabricated account
+        self.fetched_accounts.insert(address);
+        self.notify_accounts(&[address]);
+
+        Ok(account)
+    }
+
+    /// Set the amount held by an SPL Token or Token-2022 account by rewriting its
+    /// state in place, without needing the mint authority. `token_account`
+    /// defaults to the owner's associated token account, which is created if
+    /// missing. With `adjust_supply`, the mint's supply moves by the same delta.
+    pub fn set_token_balance(
+        &mut self,
+        mint: Pubkey,
+        token_account: Option<Pubkey>,
+        owner: Option<Pubkey>,
+        amount: u64,
+        adjust_supply: bool,
+    ) -> Result<SetTokenBalanceResponse, String> {
+        let mint_account = self
+            .get_account(&mint)
+            .ok_or_else(|| format!("Mint {} not found", mint))?;
+        let mint_state =
+            unpack_mint(&mint_account).ok_or_else(|| format!("{} is not an SPL token mint", mint))?;
+        let program_id = mint_account.owner;
+
+        let token_account = match (token_account, owner) {
+            (Some(token_account), _) => token_account,
+            (None, Some(owner)) => get_associated_token_address_with_program_id(&owner, &mint, &program_id),
+            (None, None) => return Err("Either a token account or an owner is required".to_string()),
+        };
+
+        let rent = self.svm.minimum_balance_for_rent_exemption(TokenAccount::LEN);
+
+        let (mut data, previous_balance, created) = match self.get_account(&token_account) {
+            Some(account) => {
+                let state = unpack_token_account(&account)
+                    .ok_or_else(|| format!("{} is not an SPL token account", token_account))?;
+                if state.mint != mint {
+                    return Err(format!("Token account {} holds mint {}, not {}", token_account, state.mint, mint));
+                }
+                (account.data, state.amount, false)
+            }
+            None => {
+                let owner = owner.ok_or_else(|| {
+                    format!("Token account {} does not exist and no owner was given to create it", token_account)
+                })?;
+                let state = TokenAccount {
+                    mint,
+                    owner,
+                    state: TokenAccountState::Initialized,
+                    is_native: if is_native_mint(&mint) { COption::Some(rent) } else { COption::None },
+                    ..Default::default()
+                };
+                let data = pack_token_account(&program_id, state, &token_extension_types::<Mint>(&mint_account))?;
+                (data, 0, true)
+            }
+        };
+
+        // Rewrite only the base state so Token-2022 extensions survive
+        let mut state = StateWithExtensionsMut::<TokenAccount>::unpack(&mut data)
+            .map_err(|e| format!("Failed to unpack token account: {:?}", e))?;
+        state.base.amount = amount;
+        state.pack_base();
+        let (owner, is_native) = (state.base.owner, state.base.is_native);
+
+        // Wrapped SOL accounts must hold their amount in lamports as well
+        let lamports = match is_native {
+            COption::Some(reserve) => Some(
+                reserve
+                    .checked_add(amount)
+                    .ok_or_else(|| "Wrapped SOL amount overflows lamports".to_string())?,
+            ),
+            COption::None if created => Some(self.svm.minimum_balance_for_rent_exemption(data.len())),
+            COption::None => None,
+        };
+
+        let mut mint_supply = mint_state.supply;
+        if adjust_supply && !is_native_mint(&mint) {
+            mint_supply = (mint_supply as i128 - previous_balance as i128 + amount as i128)
+                .try_into()
+                .map_err(|_| format!("Mint {} supply would leave the u64 range", mint))?;
+
+            let mut mint_data = mint_account.data;
+            let mut mint_state = StateWithExtensionsMut::<Mint>::unpack(&mut mint_data)
+                .map_err(|e| format!("Failed to unpack mint: {:?}", e))?;
+            mint_state.base.supply = mint_supply;
+            mint_state.pack_base();
+            self.set_account(mint, AccountUpdate { data: Some(mint_data), ..Default::default() })?;
+        }
+
+        self.set_account(
+            token_account,
+            AccountUpdate {
+                lamports,
+                owner: Some(program_id),
+                data: Some(data),
+                ..Default::default()
+            },
+        )?;
+
+        Ok(SetTokenBalanceResponse {
+            token_account: token_account.to_string(),
+            mint: mint.to_string(),
+            owner: owner.to_string(),
+            token_program_id: program_id.to_string(),
+            previous_balance,
+            balance: amount,
+            created,
+            mint_supply,
+        })
+    }
+
+    /// Deploy program bytes as an upgradeable (BPF Loader v3) program, creating or
+    /// replacing its ProgramData account. Existing programs are upgraded in place.
+    pub fn deploy_upgradeable_program(
+        &mut self,
+        program_id: Pubkey,
+        program_bytes: &[u8],
+        upgrade_authority: Option<Pubkey>,
+    ) -> Result<(), String> {
+        let (programdata_address, _) =
+            Pubkey::find_program_address(&[program_id.as_ref()], &BPF_LOADER_UPGRADEABLE_ID);
+
+        let program_data = bincode::serialize(&UpgradeableLoaderState::Program { programdata_address })
+            .map_err(|e| format!("Failed to serialize program account: {}", e))?;
+        let program_account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(program_data.len()),
+            data: program_data,
+            owner: BPF_LOADER_UPGRADEABLE_ID,
+            executable: true,
+            rent_epoch: 0,
+        };
+
+        // ProgramData metadata is padded to a fixed size, followed by the ELF bytes
+        let metadata_size = UpgradeableLoaderState::size_of_programdata_metadata();
+        let mut programdata = vec![0u8; UpgradeableLoaderState::size_of_programdata(program_bytes.len())];
+        bincode::serialize_into(
+            &mut programdata[..metadata_size],
+            &UpgradeableLoaderState::ProgramData {
+                slot: self.slot,
+                upgrade_authority_address: upgrade_authority,
+            },
+        )
+        .map_err(|e| format!("Failed to serialize program data account: {}", e))?;
+        programdata[metadata_size..].copy_from_slice(program_bytes);
+
+        let programdata_account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(programdata.len()),
+            data: programdata,
+            owner: BPF_LOADER_UPGRADEABLE_ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+
+        self.add_upgradeable_program(program_id, program_account, programdata_address, programdata_account)
+    }
+
+    pub fn add_balance(&mut self, address: &Pubkey, lamports: u64) -> Result<(), String> {
         self.svm
             .airdrop(address, lamports)
-            .map(|_| ())
-            .map_err(|e| format!("Failed to add balance: {:?}", e))
+            .map_err(|e| format!("Failed to add balance: {:?}", e))?;
+
+        self.notify_accounts(&[*address]);
+        Ok(())
     }
 
     /// Set account balance using liteSVM's airdrop
@@ -90,8 +748,10 @@ impl Fork {
             let to_add = lamports - current_balance;
             self.svm
                 .airdrop(address, to_add)
-                .map(|_| ())
-                .map_err(|e| format!("Failed to set balance: {:?}", e))
+                .map_err(|e| format!("Failed to set balance: {:?}", e))?;
+
+            self.notify_accounts(&[*address]);
+            Ok(())
         } else if lamports < current_balance {
             // Need to reduce - create account with new lower balance
             // Get existing account or create new one
@@ -110,7 +770,10 @@ impl Fork {
             
             // Update account
             self.svm.set_account(*address, account)
-                .map_err(|e| format!("Failed to set balance: {:?}", e))
+                .map_err(|e| format!("Failed to set balance: {:?}", e))?;
+
+            self.notify_accounts(&[*address]);
+            Ok(())
         } else {
             // Balance is already correct
             Ok(())
@@ -119,15 +782,14 @@ impl Fork {
 
     /// Get account balance
     pub fn get_balance(&self, address: &Pubkey) -> u64 {
-        self.svm
-            .get_account(address)
+        self.get_account(address)
             .map(|acc| acc.lamports)
             .unwrap_or(0)
     }
 
     /// Get detailed account information
     pub fn get_account_info(&self, address: &Pubkey) -> Option<AccountInfo> {
-        self.svm.get_account(address).map(|acc| AccountInfo {
+        self.get_account(address).map(|acc| AccountInfo {
             address: address.to_string(),
             lamports: acc.lamports,
             owner: acc.owner.to_string(),
@@ -137,29 +799,464 @@ impl Fork {
         })
     }
 
-    /// Send a transaction to the fork
-    pub fn send_transaction(&mut self, transaction: Transaction) -> Result<TransactionResult, String> {
-        // Get signature before sending
-        let signature = transaction.signatures[0].to_string();
-
-        // Process the transaction
-        let result = self.svm.send_transaction(transaction);
-
-        // Increment transaction count and slot
-        self.transaction_count += 1;
-        self.slot += 1;  
+    /// Send a transaction to the fork. With `include_diff`, the result also
+    /// carries how every account referenced by the transaction changed.
+    /// On a lazy loading fork, load its accounts with `load_transaction_accounts` first.
+    pub fn send_transaction(
+        &mut self,
+        transaction: impl Into<VersionedTransaction>,
+        include_diff: bool,
+    ) -> Result<TransactionResult, String> {
+        let (signature, result, account_diffs) = self.execute(transaction.into(), include_diff)?;
 
         match result {
             Ok(_) => Ok(TransactionResult {
                 success: true,
                 signature,
                 error: None,
+                account_diffs,
             }),
             Err(e) => Ok(TransactionResult {
                 success: false,
                 signature,
                 error: Some(format!("{:?}", e)),
+                account_diffs,
             }),
         }
     }
-}
\ No newline at end of file
+
+    /// Execute a transaction on the fork and return LiteSVM's full result
+    /// (logs, compute units, inner instructions, return data)
+    pub fn execute_transaction(
+        &mut self,
+        transaction: impl Into<VersionedTransaction>,
+    ) -> Result<ExecutionResult, String> {
+        let (_, result, _) = self.execute(transaction.into(), false)?;
+        Ok(result)
+    }
+
+    fn execute(
+        &mut self,
+        mut transaction: VersionedTransaction,
+        include_diff: bool,
+    ) -> Result<(String, ExecutionResult, Option<Vec<AccountDiff>>), String> {
+        if let Some(max_transactions) = self.max_transactions
+            && self.transaction_count >= max_transactions
+        {
+            return Err(format!("Transaction limit of {} reached for fork {}", max_transactions, self.id));
+        }
+
+        self.fill_impersonated_signatures(&mut transaction);
+
+        let first_signature = *transaction
+            .signatures
+            .first()
+            .ok_or("Transaction has no signatures")?;
+        let signature = first_signature.to_string();
+
+        let (loaded_writable, loaded_readonly) = self.lookup_table_addresses(&transaction.message);
+
+        // Static keys followed by loaded addresses, as the runtime orders them
+        let static_keys = transaction.message.static_account_keys();
+        let account_keys: Vec<Pubkey> = static_keys
+            .iter()
+            .chain(&loaded_writable)
+            .chain(&loaded_readonly)
+            .copied()
+            .collect();
+        let writable: Vec<bool> = (0..static_keys.len())
+            .map(|index| transaction.message.is_maybe_writable(index, None))
+            .chain(loaded_writable.iter().map(|_| true))
+            .chain(loaded_readonly.iter().map(|_| false))
+            .collect();
+
+        // Capture account state for the history record and change notifications
+        let pre_accounts: Vec<Option<Account>> =
+            account_keys.iter().map(|address| self.svm.get_account(address)).collect();
+        let pre_token_balances = self.token_balances(&account_keys);
+
+        // A signature LiteSVM already knows is rejected as a duplicate
+        let already_processed = self.svm.get_transaction(&first_signature).is_some();
+
+        // Process the transaction
+        let result = match self.sigverify_for(&transaction, self.svm.get_sigverify()) {
+            Ok(sigverify) => {
+                let original = self.set_sigverify(sigverify);
+                let check_blockhash = self.set_blockhash_check(&transaction);
+                let result = self.svm.send_transaction(transaction.clone());
+                self.restore_blockhash_check(check_blockhash);
+                self.set_sigverify(original);
+                result
+            }
+            Err(err) => Err(FailedTransactionMetadata { err, meta: TransactionMetadata::default() }),
+        };
+
+        // Increment transaction count and slot
+        self.transaction_count += 1;
+        self.advance_clock(self.slot + 1);
+
+        let post_accounts: Vec<Option<Account>> =
+            account_keys.iter().map(|address| self.svm.get_account(address)).collect();
+        let balances = |accounts: &[Option<Account>]| -> Vec<u64> {
+            accounts
+                .iter()
+                .map(|account| account.as_ref().map_or(0, |a| a.lamports))
+                .collect()
+        };
+        let pre_balances = balances(&pre_accounts);
+        let post_balances = balances(&post_accounts);
+
+        let (err, meta) = match &result {
+            Ok(meta) => (None, meta.clone()),
+            Err(failed) => (Some(failed.err.clone()), failed.meta.clone()),
+        };
+
+        // Failed transactions only land (and pay fees) if they got as far as
+        // execution, in which case LiteSVM keeps them in its own history too
+        let landed = !already_processed && self.svm.get_transaction(&first_signature).is_some();
+        if landed {
+            let fee = self.transaction_fee(&transaction.message, &loaded_writable, &loaded_readonly);
+            self.history.insert(TransactionRecord {
+                signature: signature.clone(),
+                slot: self.slot,
+                block_time: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+                transaction,
+                err: err.clone(),
+                meta: meta.clone(),
+                fee,
+                account_keys: account_keys.clone(),
+                loaded_writable,
+                loaded_readonly,
+                pre_balances,
+                post_balances,
+                pre_token_balances,
+                post_token_balances: self.token_balances(&account_keys),
+            });
+        }
+
+        if self.events.receiver_count() > 0 {
+            let changed: Vec<Pubkey> = account_keys
+                .iter()
+                .zip(writable)
+                .zip(pre_accounts.iter().zip(&post_accounts))
+                .filter(|((_, writable), (before, after))| *writable && before != after)
+                .map(|((address, _), _)| *address)
+                .collect();
+
+            // Only transactions that landed are seen by signature and logs subscribers
+            if landed {
+                let _ = self.events.send(ForkEvent::Transaction {
+                    signature: signature.clone(),
+                    err,
+                    logs: meta.logs,
+                    account_keys: account_keys.clone(),
+                    slot: self.slot,
+                });
+            }
+
+            self.notify_accounts(&changed);
+
+            let _ = self.events.send(ForkEvent::Slot { slot: self.slot });
+        }
+
+        let account_diffs =
+            include_diff.then(|| diff_accounts(&account_keys, &pre_accounts, &post_accounts));
+
+        Ok((signature, result, account_diffs))
+    }
+
+    /// Fee LiteSVM charges for a transaction: every transaction and precompile
+    /// signature at the default fee structure's rate, with no priority fee
+    fn transaction_fee(
+        &self,
+        message: &VersionedMessage,
+        loaded_writable: &[Pubkey],
+        loaded_readonly: &[Pubkey],
+    ) -> u64 {
+        let reserved_account_keys = HashSet::new();
+        let message = match message.clone() {
+            VersionedMessage::Legacy(message) => {
+                SanitizedMessage::Legacy(LegacyMessage::new(message, &reserved_account_keys))
+            }
+            VersionedMessage::V0(message) => {
+                let loaded_addresses = LoadedAddresses {
+                    writable: loaded_writable.to_vec(),
+                    readonly: loaded_readonly.to_vec(),
+                };
+                SanitizedMessage::V0(LoadedMessage::new(message, loaded_addresses, &reserved_account_keys))
+            }
+        };
+
+        solana_fee::calculate_fee(
+            &message,
+            false,
+            FeeStructure::default().lamports_per_signature,
+            0,
+            // Forks run with every feature enabled, as `LiteSVM::new` sets them
+            FeeFeatures { enable_secp256r1_precompile: true },
+        )
+    }
+
+    /// Simulate a transaction against the fork without changing its state.
+    /// `sigverify` overrides the fork's signature verification for this call only.
+    /// On a lazy loading fork, load its accounts with `load_transaction_accounts` first.
+    pub fn simulate_transaction(
+        &mut self,
+        transaction: impl Into<VersionedTransaction>,
+        sigverify: bool,
+    ) -> Result<SimulationResult, String> {
+        let mut transaction: VersionedTransaction = transaction.into();
+        self.fill_impersonated_signatures(&mut transaction);
+
+        let result = match self.sigverify_for(&transaction, sigverify) {
+            Ok(sigverify) => {
+                let original = self.set_sigverify(sigverify);
+                let check_blockhash = self.set_blockhash_check(&transaction);
+                let result = self.svm.simulate_transaction(transaction);
+                self.restore_blockhash_check(check_blockhash);
+                self.set_sigverify(original);
+                result
+            }
+            Err(err) => Err(FailedTransactionMetadata { err, meta: TransactionMetadata::default() }),
+        };
+
+        Ok(result)
+    }
+
+    /// Blockhash clients should build transactions with
+    pub fn latest_blockhash(&self) -> Hash {
+        self.blockhash
+    }
+
+    /// Install mainnet's sysvar accounts as of `mainnet_slot`, so programs see
+    /// mainnet's clock, rent, epoch schedule, slot hashes and recent blockhashes
+    pub async fn sync_sysvars(&mut self, client: &MainnetClient) -> Result<(), String> {
+        let accounts = client.fetch_accounts_at_slot(&MAINNET_SYSVARS, self.mainnet_slot).await?;
+
+        for (address, account) in MAINNET_SYSVARS.iter().zip(accounts) {
+            let Some(account) = account else {
+                continue;
+            };
+            self.svm
+                .set_account(*address, account)
+                .map_err(|e| format!("Failed to install sysvar {}: {:?}", address, e))?;
+            self.fetched_accounts.insert(*address);
+        }
+
+        // Sysvars may come from a slightly newer slot; line the clock up with the fork
+        self.advance_clock(self.slot);
+
+        Ok(())
+    }
+
+    /// The fork's Clock sysvar, as programs see it
+    pub fn clock(&self) -> Clock {
+        self.svm.get_sysvar::<Clock>()
+    }
+
+    /// Move the fork forward to `slot`. The Clock (slot, epoch and timestamp)
+    /// follows, SlotHashes gets an entry for the new slot and a new blockhash
+    /// is issued, as if the skipped slots had been produced.
+    pub fn warp_to_slot(&mut self, slot: u64) -> Result<(), String> {
+        if slot < self.slot {
+            return Err(format!("Cannot warp backwards from slot {} to {}", self.slot, slot));
+        }
+
+        let elapsed_ms = (slot - self.slot).saturating_mul(MS_PER_SLOT);
+        let mut clock = self.clock();
+        clock.unix_timestamp = clock
+            .unix_timestamp
+            .saturating_add((elapsed_ms / 1000).min(i64::MAX as u64) as i64);
+        self.svm.set_sysvar(&clock);
+
+        self.svm.expire_blockhash();
+        self.blockhash = self.svm.latest_blockhash();
+        self.advance_clock(slot);
+
+        let _ = self.events.send(ForkEvent::Slot { slot: self.slot });
+
+        Ok(())
+    }
+
+    /// Move the fork forward by `slots` slots
+    pub fn advance_slots(&mut self, slots: u64) -> Result<(), String> {
+        let slot = self.slot.checked_add(slots).ok_or("Slot overflow")?;
+        self.warp_to_slot(slot)
+    }
+
+    /// Move the fork forward to the first slot of `epoch`
+    pub fn warp_to_epoch(&mut self, epoch: u64) -> Result<(), String> {
+        let epoch_schedule = self.svm.get_sysvar::<EpochSchedule>();
+        let slot = epoch_schedule.get_first_slot_in_epoch(epoch);
+        if slot < self.slot {
+            return Err(format!(
+                "Cannot warp backwards to epoch {}, the fork is already in epoch {}",
+                epoch,
+                epoch_schedule.get_epoch(self.slot)
+            ));
+        }
+
+        self.warp_to_slot(slot)
+    }
+
+    /// Set the Clock's `unix_timestamp` without changing the slot
+    pub fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Set `Fork.slot` and bring the Clock's slot and epoch and SlotHashes in
+    /// line with it. The blockhash is kept, so clients can keep using it.
+    fn advance_clock(&mut self, slot: u64) {
+        let epoch_schedule = self.svm.get_sysvar::<EpochSchedule>();
+        let mut clock = self.clock();
+
+        let epoch = epoch_schedule.get_epoch(slot);
+        if epoch != clock.epoch {
+            clock.epoch_start_timestamp = clock.unix_timestamp;
+        }
+        clock.slot = slot;
+        clock.epoch = epoch;
+        clock.leader_schedule_epoch = epoch_schedule.get_leader_schedule_epoch(slot);
+        self.svm.set_sysvar(&clock);
+
+        let mut slot_hashes = self.svm.get_sysvar::<SlotHashes>();
+        slot_hashes.add(slot, self.blockhash);
+        self.svm.set_sysvar(&slot_hashes);
+
+        self.slot = slot;
+    }
+
+    /// Treat `address` as a valid signer of every transaction on this fork.
+    /// Returns false if it was already impersonated.
+    pub fn impersonate(&mut self, address: Pubkey) -> bool {
+        self.impersonated.insert(address)
+    }
+
+    /// Require real signatures from `address` again.
+    /// Returns false if it was not impersonated.
+    pub fn stop_impersonating(&mut self, address: &Pubkey) -> bool {
+        self.impersonated.remove(address)
+    }
+
+    /// Accounts currently impersonated on this fork, sorted
+    pub fn impersonated_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts: Vec<Pubkey> = self.impersonated.iter().copied().collect();
+        accounts.sort();
+        accounts
+    }
+
+    /// Execute a transaction as if every required signer had signed it,
+    /// without impersonating them for later transactions
+    pub fn send_impersonated_transaction(
+        &mut self,
+        transaction: impl Into<VersionedTransaction>,
+        include_diff: bool,
+    ) -> Result<TransactionResult, String> {
+        let transaction: VersionedTransaction = transaction.into();
+
+        let num_signers = transaction.message.header().num_required_signatures as usize;
+        let added: Vec<Pubkey> = transaction
+            .message
+            .static_account_keys()
+            .iter()
+            .take(num_signers)
+            .filter(|signer| self.impersonated.insert(**signer))
+            .copied()
+            .collect();
+
+        let result = self.send_transaction(transaction, include_diff);
+
+        for signer in &added {
+            self.impersonated.remove(signer);
+        }
+
+        result
+    }
+
+    /// Give impersonated signers that left their signature blank a placeholder
+    /// derived from the message and the signer, so simulating and then sending
+    /// the same transaction yields the same signature
+    fn fill_impersonated_signatures(&self, transaction: &mut VersionedTransaction) {
+        if self.impersonated.is_empty() {
+            return;
+        }
+
+        let num_signers = transaction.message.header().num_required_signatures as usize;
+        if transaction.signatures.len() < num_signers {
+            transaction.signatures.resize(num_signers, Signature::default());
+        }
+
+        let message_hash = hash(&transaction.message.serialize());
+        for (signature, signer) in transaction
+            .signatures
+            .iter_mut()
+            .zip(transaction.message.static_account_keys())
+        {
+            if *signature == Signature::default() && self.impersonated.contains(signer) {
+                let mut placeholder = [0u8; 64];
+                placeholder[..32].copy_from_slice(message_hash.as_ref());
+                placeholder[32..].copy_from_slice(signer.as_ref());
+                *signature = Signature::from(placeholder);
+            }
+        }
+    }
+
+    /// Signature verification the SVM should run for `transaction`. When an
+    /// impersonated account is among the signers, the other signatures are
+    /// checked here instead and the SVM skips verification.
+    fn sigverify_for(
+        &self,
+        transaction: &VersionedTransaction,
+        sigverify: bool,
+    ) -> Result<bool, TransactionError> {
+        let num_signers = transaction.message.header().num_required_signatures as usize;
+        let signers = transaction.message.static_account_keys().iter().take(num_signers);
+
+        if !sigverify || !signers.clone().any(|signer| self.impersonated.contains(signer)) {
+            return Ok(sigverify);
+        }
+
+        let all_signed = transaction.signatures.len() == num_signers
+            && signers
+                .zip(transaction.verify_with_results())
+                .all(|(signer, verified)| verified || self.impersonated.contains(signer));
+
+        if all_signed {
+            Ok(false)
+        } else {
+            Err(TransactionError::SignatureFailure)
+        }
+    }
+
+    /// Turn off the SVM's blockhash check for a transaction built with the
+    /// advertised blockhash when the VM does not know it. Returns whether the
+    /// check has to be turned back on.
+    fn set_blockhash_check(&mut self, transaction: &VersionedTransaction) -> bool {
+        let skip = *transaction.message.recent_blockhash() == self.blockhash
+            && self.blockhash != self.svm.latest_blockhash();
+        if skip {
+            self.svm = std::mem::take(&mut self.svm).with_blockhash_check(false);
+        }
+        skip
+    }
+
+    fn restore_blockhash_check(&mut self, check_blockhash: bool) {
+        if check_blockhash {
+            self.svm = std::mem::take(&mut self.svm).with_blockhash_check(true);
+        }
+    }
+
+    /// Set the SVM's signature verification, returning the previous setting
+    fn set_sigverify(&mut self, sigverify: bool) -> bool {
+        let original = self.svm.get_sigverify();
+        if original != sigverify {
+            self.svm = std::mem::take(&mut self.svm).with_sigverify(sigverify);
+        }
+        original
+    }
+}
@@ -0,0 +1,74 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use solana_account::Account;
+use solana_pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Version of the fork state file format
+pub const FORK_STATE_VERSION: u32 = 1;
+
+/// Serializable snapshot of a fork, used for on-disk persistence and export/import
+#[derive(Serialize, Deserialize)]
+pub struct ForkState {
+    pub version: u32,
+    pub fork_id: String,
+    pub user_id: Option<String>,
+    pub created_at: u64,
+    pub slot: u64,
+    pub mainnet_slot: u64,
+    pub mainnet_blockhash: String,
+    pub transaction_count: u64,
+    /// Blockhash handed to clients
+    #[serde(default)]
+    pub blockhash: Option<String>,
+    pub rpc_endpoint: Option<String>,  // upstream for lazy loading, if enabled
+    pub fetched_accounts: Vec<String>,
+    /// Accounts treated as valid signers without a signature
+    #[serde(default)]
+    pub impersonated: Vec<String>,
+    pub accounts: Vec<AccountState>,
+}
+
+/// Serializable account, with data encoded as base64
+#[derive(Serialize, Deserialize)]
+pub struct AccountState {
+    pub address: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: String,
+}
+
+impl AccountState {
+    pub fn from_account(address: &Pubkey, account: &Account) -> Self {
+        Self {
+            address: address.to_string(),
+            lamports: account.lamports,
+            owner: account.owner.to_string(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: BASE64.encode(&account.data),
+        }
+    }
+
+    pub fn to_account(&self) -> Result<(Pubkey, Account), String> {
+        let address = Pubkey::from_str(&self.address)
+            .map_err(|e| format!("Invalid address {}: {}", self.address, e))?;
+        let owner = Pubkey::from_str(&self.owner)
+            .map_err(|e| format!("Invalid owner {}: {}", self.owner, e))?;
+        let data = BASE64.decode(&self.data)
+            .map_err(|e| format!("Invalid account data for {}: {}", self.address, e))?;
+
+        Ok((
+            address,
+            Account {
+                lamports: self.lamports,
+                data,
+                owner,
+                executable: self.executable,
+                rent_epoch: self.rent_epoch,
+            },
+        ))
+    }
+}
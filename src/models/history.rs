@@ -0,0 +1,229 @@
+use litesvm::types::TransactionMetadata;
+use solana_pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction::versioned::VersionedTransaction;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use crate::constants::MAX_TRANSACTION_HISTORY;
+
+/// SPL token balance of one of a transaction's accounts
+#[derive(Debug, Clone)]
+pub struct TokenBalance {
+    pub account_index: usize,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub program_id: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+/// A transaction executed on a fork, with everything `getTransaction` reports
+#[derive(Debug, Clone)]
+pub struct TransactionRecord {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: u64,
+    pub transaction: VersionedTransaction,
+    pub err: Option<TransactionError>,
+    /// Logs, compute units, inner instructions and return data
+    pub meta: TransactionMetadata,
+    pub fee: u64,
+    /// Static account keys followed by addresses loaded from lookup tables;
+    /// balances below are indexed the same way
+    pub account_keys: Vec<Pubkey>,
+    /// Addresses loaded from address lookup tables by a v0 message
+    pub loaded_writable: Vec<Pubkey>,
+    pub loaded_readonly: Vec<Pubkey>,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub pre_token_balances: Vec<TokenBalance>,
+    pub post_token_balances: Vec<TokenBalance>,
+}
+
+/// The latest `MAX_TRANSACTION_HISTORY` executed transactions of a fork,
+/// indexed by signature and by account. Records are shared, so snapshots
+/// and clones of the history do not copy them.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionHistory {
+    records: HashMap<String, Arc<TransactionRecord>>,
+    /// Signatures in execution order
+    order: VecDeque<String>,
+    /// Signatures referencing each address, in execution order
+    by_address: HashMap<Pubkey, VecDeque<String>>,
+}
+
+impl TransactionHistory {
+    pub fn insert(&mut self, record: TransactionRecord) {
+        // A signature can only land once; keep the first execution
+        if self.records.contains_key(&record.signature) {
+            return;
+        }
+
+        if self.order.len() >= MAX_TRANSACTION_HISTORY {
+            self.evict_oldest();
+        }
+
+        for address in &record.account_keys {
+            self.by_address
+                .entry(*address)
+                .or_default()
+                .push_back(record.signature.clone());
+        }
+        self.order.push_back(record.signature.clone());
+        self.records.insert(record.signature.clone(), Arc::new(record));
+    }
+
+    /// Drop the oldest transaction. It is also the oldest of every address it references.
+    fn evict_oldest(&mut self) {
+        let Some(signature) = self.order.pop_front() else {
+            return;
+        };
+        let Some(record) = self.records.remove(&signature) else {
+            return;
+        };
+
+        for address in &record.account_keys {
+            if let Some(signatures) = self.by_address.get_mut(address) {
+                if signatures.front() == Some(&signature) {
+                    signatures.pop_front();
+                }
+                if signatures.is_empty() {
+                    self.by_address.remove(address);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, signature: &str) -> Option<&TransactionRecord> {
+        self.records.get(signature).map(Arc::as_ref)
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// All transactions, newest first
+    pub fn iter_newest_first(&self) -> impl Iterator<Item = &TransactionRecord> {
+        self.order.iter().rev().filter_map(|signature| self.get(signature))
+    }
+
+    /// Transactions that reference `address`, newest first, starting after
+    /// `before` and stopping at `until` (both exclusive), at most `limit` entries
+    pub fn signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<&str>,
+        until: Option<&str>,
+        limit: usize,
+    ) -> Vec<&TransactionRecord> {
+        let Some(signatures) = self.by_address.get(address) else {
+            return Vec::new();
+        };
+
+        let mut newest_first = signatures.iter().rev();
+        if let Some(before) = before {
+            // Skip up to and including `before`; an unknown signature yields nothing
+            if !signatures.iter().any(|s| s == before) {
+                return Vec::new();
+            }
+            for signature in newest_first.by_ref() {
+                if signature == before {
+                    break;
+                }
+            }
+        }
+
+        newest_first
+            .take_while(|signature| Some(signature.as_str()) != until)
+            .filter_map(|signature| self.get(signature))
+            .take(limit)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Fork;
+    use solana_keypair::Keypair;
+    use solana_message::Message;
+    use solana_signer::Signer;
+    use solana_system_interface::instruction as system_instruction;
+    use solana_transaction::Transaction;
+
+    fn record(signature: &str, account_keys: Vec<Pubkey>) -> TransactionRecord {
+        TransactionRecord {
+            signature: signature.to_string(),
+            slot: 0,
+            block_time: 0,
+            transaction: VersionedTransaction::default(),
+            err: None,
+            meta: TransactionMetadata::default(),
+            fee: 0,
+            account_keys,
+            loaded_writable: Vec::new(),
+            loaded_readonly: Vec::new(),
+            pre_balances: Vec::new(),
+            post_balances: Vec::new(),
+            pre_token_balances: Vec::new(),
+            post_token_balances: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn drops_oldest_transaction_at_capacity() {
+        let first_only = Pubkey::new_unique();
+        let shared = Pubkey::new_unique();
+        let mut history = TransactionHistory::default();
+
+        history.insert(record("sig-0", vec![first_only, shared]));
+        for index in 1..=MAX_TRANSACTION_HISTORY {
+            history.insert(record(&format!("sig-{}", index), vec![shared]));
+        }
+
+        assert_eq!(history.len(), MAX_TRANSACTION_HISTORY);
+        assert!(history.get("sig-0").is_none());
+        assert!(history.get("sig-1").is_some());
+        assert!(history.signatures_for_address(&first_only, None, None, 10).is_empty());
+
+        let shared_signatures = history.signatures_for_address(&shared, None, None, usize::MAX);
+        assert_eq!(shared_signatures.len(), MAX_TRANSACTION_HISTORY);
+        assert_eq!(shared_signatures.last().unwrap().signature, "sig-1");
+    }
+
+    fn transfer(fork: &mut Fork, payer: &Keypair, lamports: u64) -> String {
+        let blockhash = fork.latest_blockhash();
+        let message = Message::new_with_blockhash(
+            &[system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), lamports)],
+            Some(&payer.pubkey()),
+            &blockhash,
+        );
+        let result = fork.send_transaction(Transaction::new(&[payer], message, blockhash), false).unwrap();
+        assert!(result.success);
+        result.signature
+    }
+
+    #[test]
+    fn revert_restores_history() {
+        let mut fork = Fork::new("fork-history".to_string());
+        let payer = Keypair::new();
+        fork.add_balance(&payer.pubkey(), 1_000_000_000).unwrap();
+
+        let kept = transfer(&mut fork, &payer, 1_000);
+        let snapshot = fork.snapshot("snapshot-1".to_string());
+        let reverted = transfer(&mut fork, &payer, 2_000);
+        assert_eq!(fork.history.len(), 2);
+
+        fork.revert(&snapshot);
+
+        assert_eq!(fork.history.len(), 1);
+        assert!(fork.history.get(&kept).is_some());
+        assert!(fork.history.get(&reverted).is_none());
+        assert_eq!(fork.history.signatures_for_address(&payer.pubkey(), None, None, 10).len(), 1);
+    }
+}
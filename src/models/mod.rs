@@ -1,11 +1,21 @@
+pub mod account_update;
+pub mod diff;
+pub mod event;
 pub mod fork;
+pub mod fork_state;
+pub mod history;
 pub mod requests;
 pub mod responses;
 pub mod token;
 pub mod program;
 pub mod rpc;
 
+pub use account_update::*;
+pub use diff::*;
+pub use event::*;
 pub use fork::*;
+pub use fork_state::*;
+pub use history::*;
 pub use requests::*;
 pub use responses::*;
 pub use token::*;
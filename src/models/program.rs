@@ -67,6 +67,15 @@ pub struct DeployProgramResponse {
     pub deployed_size: usize,
 }
 
+/// Response after upgrading a program
+#[derive(Serialize)]
+pub struct UpgradeProgramResponse {
+    pub program_id: String,
+    pub success: bool,
+    pub program_size: usize,
+    pub upgrade_authority: String,
+}
+
 /// Response after invoking a program
 #[derive(Serialize)]
 pub struct InvokeProgramResponse {
@@ -74,6 +83,29 @@ pub struct InvokeProgramResponse {
     pub success: bool,
     pub logs: Vec<String>,
     pub error: Option<String>,
+    pub compute_units_consumed: u64,
+    pub return_data: Option<ProgramReturnData>,
+    pub inner_instructions: Vec<InnerInstructionData>,
+    pub failed_instruction_index: Option<u8>,  // set when an instruction failed
+    pub instruction_error: Option<String>,     // e.g. "custom program error: 0x1771"
+    pub custom_error_code: Option<u32>,        // set for InstructionError::Custom
+}
+
+/// Data a program set with `set_return_data`
+#[derive(Serialize)]
+pub struct ProgramReturnData {
+    pub program_id: String,
+    pub data: String,  // base64 encoded
+}
+
+/// Cross-program invocation made while executing the instruction
+#[derive(Serialize)]
+pub struct InnerInstructionData {
+    pub instruction_index: usize,  // top-level instruction that made the call
+    pub stack_height: u8,
+    pub program_id: String,
+    pub accounts: Vec<String>,
+    pub data: String,  // base64 encoded
 }
 
 /// Response after loading a program from mainnet
@@ -83,6 +115,8 @@ pub struct LoadProgramResponse {
     pub success: bool,
     pub program_size: usize,
     pub is_executable: bool,
+    pub programdata_address: Option<String>,  // set for BPF Loader v3 programs
+    pub upgrade_authority: Option<String>,
 }
 
 /// Program account information
@@ -93,4 +127,6 @@ pub struct ProgramInfo {
     pub owner: String,
     pub data_size: usize,
     pub lamports: u64,
+    pub programdata_address: Option<String>,  // set for BPF Loader v3 programs
+    pub upgrade_authority: Option<String>,
 }
\ No newline at end of file
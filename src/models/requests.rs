@@ -1,17 +1,27 @@
 use serde::Deserialize;
 
+use crate::models::{AccountMetaData, ForkState};
+
 /// Request to create a new fork
 #[derive(Deserialize)]
 pub struct CreateForkRequest {
     pub user_id: Option<String>,
 }
 
+/// Request to clone an existing fork
+#[derive(Deserialize)]
+pub struct CloneForkRequest {
+    pub user_id: Option<String>,
+}
+
 /// Request to create a fork from mainnet with specific accounts
 #[derive(Deserialize)]
 pub struct CreateMainnetForkRequest {
     pub user_id: Option<String>,
     pub accounts: Vec<String>, 
     pub rpc_endpoint: Option<String>, 
+    /// Fetch any account missing from the fork from mainnet on first access
+    pub lazy_load: Option<bool>,
 }
 
 /// Request to load a single account from mainnet into fork
@@ -50,6 +60,32 @@ pub struct SetBalanceRequest {
     pub lamports: u64,
 }
 
+/// Request to write an account's state directly.
+/// Omitted fields keep their current value.
+#[derive(Deserialize)]
+pub struct SetAccountRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub address: String,
+    pub lamports: Option<u64>,
+    pub owner: Option<String>,
+    pub executable: Option<bool>,
+    pub rent_epoch: Option<u64>,
+    /// Replaces the whole account data
+    pub data: Option<String>,
+    /// Encoding of `data` and `patches`: "base64" (default), "base58" or "hex"
+    pub encoding: Option<String>,
+    /// Byte ranges written after `data`, growing the account as needed
+    pub patches: Option<Vec<DataPatchRequest>>,
+}
+
+/// Bytes to write at an offset of an account's data
+#[derive(Deserialize)]
+pub struct DataPatchRequest {
+    pub offset: usize,
+    pub data: String,
+}
+
 /// Request to get account balance
 #[derive(Deserialize)]
 pub struct GetBalanceRequest {
@@ -73,6 +109,8 @@ pub struct SendTransactionRequest {
     pub fork_id: Option<String>,
     pub user_id: Option<String>,
     pub transaction: String, // Base64 encoded transaction
+    /// Return how every account referenced by the transaction changed
+    pub include_diff: Option<bool>,
 }
 
 /// Request to transfer SOL between accounts
@@ -84,5 +122,89 @@ pub struct TransferRequest {
     pub to: String,
     pub amount_sol: f64,
     pub private_key: String,
+    /// Return how the sender and recipient accounts changed
+    pub include_diff: Option<bool>,
 }
 
+/// Request to start or stop impersonating an account
+#[derive(Deserialize)]
+pub struct ImpersonateRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub address: String,
+}
+
+/// Request to execute a transaction without its signers' private keys.
+/// Either `transaction` or `instructions` (with `fee_payer`) must be given.
+#[derive(Deserialize)]
+pub struct SendImpersonatedRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub transaction: Option<String>, // Base64 encoded, signatures may be blank
+    pub instructions: Option<Vec<InstructionRequest>>,
+    pub fee_payer: Option<String>,
+    /// Return how every account referenced by the transaction changed
+    pub include_diff: Option<bool>,
+}
+
+/// A single instruction of an impersonated transaction
+#[derive(Deserialize)]
+pub struct InstructionRequest {
+    pub program_id: String,
+    pub accounts: Vec<AccountMetaData>,
+    pub data: String, // Base64 encoded instruction data
+}
+
+/// Request to warp a fork forward to a slot
+#[derive(Deserialize)]
+pub struct WarpSlotRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub slot: u64,
+}
+
+/// Request to advance a fork by a number of slots
+#[derive(Deserialize)]
+pub struct AdvanceSlotsRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub slots: u64,
+}
+
+/// Request to warp a fork forward to the start of an epoch
+#[derive(Deserialize)]
+pub struct WarpEpochRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub epoch: u64,
+}
+
+/// Request to set the Clock sysvar's unix timestamp
+#[derive(Deserialize)]
+pub struct SetTimestampRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub unix_timestamp: i64,
+}
+
+/// Request to snapshot a fork's current state
+#[derive(Deserialize)]
+pub struct SnapshotRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+}
+
+/// Request to revert a fork to a snapshot
+#[derive(Deserialize)]
+pub struct RevertRequest {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub snapshot_id: String,
+}
+
+/// Request to import a fork from an exported state file
+#[derive(Deserialize)]
+pub struct ImportForkRequest {
+    pub user_id: Option<String>,
+    pub state: ForkState,
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::diff::AccountDiff;
+
 /// Response after creating a fork
 #[derive(Serialize)]
 pub struct CreateForkResponse {
@@ -9,6 +11,29 @@ pub struct CreateForkResponse {
     pub rpc_url: String,
 }
 
+/// Response after cloning a fork
+#[derive(Serialize)]
+pub struct CloneForkResponse {
+    pub success: bool,
+    pub message: String,
+    pub fork_id: String,
+    pub source_fork_id: String,
+    pub user_id: String,
+    pub slot: u64,
+    pub rpc_url: String,
+}
+
+/// Response after importing a fork
+#[derive(Serialize)]
+pub struct ImportForkResponse {
+    pub success: bool,
+    pub fork_id: String,
+    pub user_id: String,
+    pub slot: u64,
+    pub accounts_loaded: usize,
+    pub rpc_url: String,
+}
+
 /// Fork information summary
 #[derive(Serialize)]
 pub struct ForkInfo {
@@ -64,6 +89,8 @@ pub struct SendTransactionResponse {
     pub success: bool,
     pub signature: String,
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_diffs: Option<Vec<AccountDiff>>,
 }
 
 /// Response after SOL transfer
@@ -76,6 +103,8 @@ pub struct TransferResponse {
     pub amount_sol: f64,
     pub amount_lamports: u64,
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_diffs: Option<Vec<AccountDiff>>,
 }
 
 /// Transaction execution result
@@ -84,6 +113,30 @@ pub struct TransactionResult {
     pub success: bool,
     pub signature: String,
     pub error: Option<String>,
+    /// Per-account changes, when requested
+    pub account_diffs: Option<Vec<AccountDiff>>,
+}
+
+/// Summary of an executed transaction in a fork's history
+#[derive(Serialize)]
+pub struct TransactionSummary {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: u64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub fee: u64,
+    pub compute_units_consumed: u64,
+    pub accounts: Vec<String>,
+    pub logs: Vec<String>,
+}
+
+/// Response listing a fork's transactions, newest first
+#[derive(Serialize)]
+pub struct TransactionListResponse {
+    pub fork_id: String,
+    pub total: usize,
+    pub transactions: Vec<TransactionSummary>,
 }
 
 // ============================================
@@ -101,6 +154,8 @@ pub struct CreateMainnetForkResponse {
     pub mainnet_blockhash: String,   // ← NEW
     pub accounts_loaded: usize,
     pub loaded_addresses: Vec<String>,
+    pub lazy_load: bool,
+    pub rpc_url: String,
 }
 
 /// Response for loading accounts
@@ -109,4 +164,58 @@ pub struct LoadAccountsResponse {
     pub success: bool,
     pub accounts_loaded: usize,
     pub loaded_addresses: Vec<String>,
-}
\ No newline at end of file
+}
+
+// ============================================
+// SNAPSHOT RESPONSES
+// ============================================
+
+/// Response after snapshotting a fork
+#[derive(Serialize)]
+pub struct SnapshotResponse {
+    pub success: bool,
+    pub fork_id: String,
+    pub snapshot_id: String,
+    pub slot: u64,
+}
+
+/// Response after starting or stopping impersonation
+#[derive(Serialize)]
+pub struct ImpersonateResponse {
+    pub success: bool,
+    pub fork_id: String,
+    pub address: String,
+    /// Every account impersonated on the fork after the change
+    pub impersonated: Vec<String>,
+}
+
+/// A fork's clock after a time travel operation
+#[derive(Serialize)]
+pub struct ClockResponse {
+    pub success: bool,
+    pub fork_id: String,
+    pub slot: u64,
+    pub epoch: u64,
+    pub unix_timestamp: i64,
+    pub blockhash: String,
+}
+
+/// Response after reverting a fork to a snapshot
+#[derive(Serialize)]
+pub struct RevertResponse {
+    pub success: bool,
+    pub fork_id: String,
+    pub snapshot_id: String,
+    pub slot: u64,
+}
+
+// ============================================
+// ADMIN RESPONSES
+// ============================================
+
+/// Response after clearing the shared account cache
+#[derive(Serialize)]
+pub struct ClearAccountCacheResponse {
+    pub success: bool,
+    pub entries_removed: usize,
+}
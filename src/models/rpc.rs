@@ -1,30 +1,118 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 #[derive(Debug, Deserialize)]
 pub struct RpcRequest {
     pub jsonrpc: String,
-    pub id: u64,
+    /// String, number or null; omitted ids are answered as null
+    #[serde(default)]
+    pub id: Value,
     pub method: String,
-    pub params: Option<Vec<Value>>,
+    pub params: Option<RpcParams>,
+}
+
+/// JSON-RPC params, given either by position or by name
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum RpcParams {
+    Positional(Vec<Value>),
+    Named(Map<String, Value>),
+}
+
+impl RpcParams {
+    /// Convert to positional params using the method's parameter names.
+    /// Methods without known names receive the object as their only parameter.
+    pub fn into_positional(self, names: &[&str]) -> Vec<Value> {
+        match self {
+            RpcParams::Positional(params) => params,
+            RpcParams::Named(params) if names.is_empty() => vec![Value::Object(params)],
+            RpcParams::Named(mut params) => {
+                let mut positional: Vec<Value> = names
+                    .iter()
+                    .map(|name| params.remove(*name).unwrap_or(Value::Null))
+                    .collect();
+
+                // Drop trailing params that were not given
+                while positional.last().is_some_and(Value::is_null) {
+                    positional.pop();
+                }
+
+                positional
+            }
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct RpcResponse {
     pub jsonrpc: String,
-    pub id: u64,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<RpcError>,
 }
 
+/// Body of a JSON-RPC reply: one response, or one per request in a batch
 #[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum RpcResponseBody {
+    Single(RpcResponse),
+    Batch(Vec<RpcResponse>),
+}
+
+/// PubSub notification pushed to a WebSocket subscriber
+#[derive(Debug, Serialize)]
+pub struct RpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    pub params: RpcNotificationParams,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RpcNotificationParams {
+    pub result: Value,
+    pub subscription: u64,
+}
+
+impl RpcNotification {
+    pub fn new(method: &str, subscription: u64, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: RpcNotificationParams {
+                result,
+                subscription,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct RpcError {
     pub code: i32,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i32, message: String) -> Self {
+        Self {
+            code,
+            message,
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
 }
 
 impl RpcResponse {
-    pub fn success(id: u64, result: Value) -> Self {
+    pub fn success(id: Value, result: Value) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             id,
@@ -33,12 +121,16 @@ impl RpcResponse {
         }
     }
 
-    pub fn error(id: u64, code: i32, message: String) -> Self {
+    pub fn error(id: Value, code: i32, message: String) -> Self {
+        Self::from_error(id, RpcError::new(code, message))
+    }
+
+    pub fn from_error(id: Value, error: RpcError) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             id,
             result: None,
-            error: Some(RpcError { code, message }),
+            error: Some(error),
         }
     }
-}
\ No newline at end of file
+}
@@ -6,11 +6,24 @@ pub struct CreateTokenRequest {
     pub user_id: String,
     pub payer_keypair: String,  // base58 encoded keypair
     pub decimals: u8,
+    pub token_program_id: Option<String>,  // defaults to SPL Token
+    // Token-2022 extensions, with the payer as their authority
+    pub transfer_fee: Option<TransferFeeConfigRequest>,
+    pub interest_rate: Option<i16>,  // basis points per year
+    pub metadata_address: Option<String>,  // metadata pointer target
+    pub transfer_hook_program_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TransferFeeConfigRequest {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
 }
 
 #[derive(Serialize)]
 pub struct CreateTokenResponse {
     pub mint_address: String,
+    pub token_program_id: String,
     pub signature: String,
 }
 
@@ -21,11 +34,13 @@ pub struct CreateTokenAccountRequest {
     pub payer_keypair: String,  // base58 encoded keypair
     pub mint_address: String,
     pub owner_address: String,
+    pub token_program_id: Option<String>,  // defaults to the mint's program
 }
 
 #[derive(Serialize)]
 pub struct CreateTokenAccountResponse {
     pub token_account: String,
+    pub token_program_id: String,
     pub signature: String,
 }
 
@@ -37,6 +52,7 @@ pub struct MintTokensRequest {
     pub mint_address: String,
     pub destination_account: String,
     pub amount: u64,
+    pub token_program_id: Option<String>,  // defaults to the mint's program
 }
 
 #[derive(Serialize)]
@@ -53,6 +69,7 @@ pub struct TransferTokensRequest {
     pub source_account: String,
     pub destination_account: String,
     pub amount: u64,
+    pub token_program_id: Option<String>,  // defaults to the source account's program
 }
 
 #[derive(Serialize)]
@@ -60,6 +77,7 @@ pub struct TransferTokensResponse {
     pub signature: String,
     pub source_balance: u64,
     pub destination_balance: u64,
+    pub fee: u64,  // Token-2022 transfer fee withheld from the amount
 }
 
 // Get Token Balance Request
@@ -67,6 +85,7 @@ pub struct TransferTokensResponse {
 pub struct GetTokenBalanceRequest {
     pub user_id: String,
     pub token_account: String,
+    pub token_program_id: Option<String>,  // defaults to the account's program
 }
 
 #[derive(Serialize)]
@@ -75,4 +94,68 @@ pub struct GetTokenBalanceResponse {
     pub balance: u64,
     pub mint: String,
     pub owner: String,
-}
\ No newline at end of file
+    pub token_program_id: String,
+    pub decimals: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<TokenExtensionsInfo>,
+}
+
+// Decoded Token-2022 extension state of a token account and its mint
+#[derive(Serialize)]
+pub struct TokenExtensionsInfo {
+    pub account_extensions: Vec<String>,
+    pub mint_extensions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer_fee: Option<TransferFeeInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interest_bearing: Option<InterestBearingInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_pointer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<TokenMetadataInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer_hook_program_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TransferFeeInfo {
+    pub basis_points: u16,  // fee for the fork's current epoch
+    pub maximum_fee: u64,
+    pub withheld_amount: u64,  // withheld in this token account
+}
+
+#[derive(Serialize)]
+pub struct InterestBearingInfo {
+    pub rate: i16,  // basis points per year
+    pub ui_amount: Option<String>,  // balance with interest accrued up to the fork's clock
+}
+
+#[derive(Serialize)]
+pub struct TokenMetadataInfo {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+// Set Token Balance Request
+#[derive(Deserialize)]
+pub struct SetTokenBalanceRequest {
+    pub user_id: String,
+    pub mint_address: String,
+    pub owner_address: Option<String>,  // ATA owner, required to create a missing account
+    pub token_account: Option<String>,  // defaults to the owner's ATA
+    pub amount: u64,
+    pub adjust_supply: Option<bool>,  // keep the mint's supply consistent, default false
+    pub token_program_id: Option<String>,  // defaults to the mint's program
+}
+
+#[derive(Serialize)]
+pub struct SetTokenBalanceResponse {
+    pub token_account: String,
+    pub mint: String,
+    pub owner: String,
+    pub token_program_id: String,
+    pub previous_balance: u64,
+    pub balance: u64,
+    pub created: bool,
+    pub mint_supply: u64,
+}
@@ -0,0 +1,20 @@
+use axum::{extract::State, Json};
+
+use crate::{
+    models::ClearAccountCacheResponse,
+    services::AccountCacheStats,
+    state::AppState,
+};
+
+/// Size, hit rate and eviction counters of the shared upstream account cache
+pub async fn get_account_cache_stats(State(state): State<AppState>) -> Json<AccountCacheStats> {
+    Json(state.account_cache.stats())
+}
+
+/// Drop every cached upstream account, so the next fetches go to upstream
+pub async fn clear_account_cache(State(state): State<AppState>) -> Json<ClearAccountCacheResponse> {
+    Json(ClearAccountCacheResponse {
+        success: true,
+        entries_removed: state.account_cache.clear(),
+    })
+}
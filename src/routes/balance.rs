@@ -10,15 +10,19 @@ use std::str::FromStr;
 use crate::{
     models::{
         AccountInfo,
+        AccountUpdate,
         AirdropRequest,
+        DataPatch,
         AirdropResponse,
         GetBalanceRequest,
         GetBalanceResponse,
+        SetAccountRequest,
         SetBalanceRequest,
         SetBalanceResponse,
     },
+    services::load_accounts,
     state::AppState,
-    utils::{lamports_to_sol, sol_to_lamports},
+    utils::{decode_account_data, lamports_to_sol, resolve_fork_or_user_id, sol_to_lamports},
 };
 
 // Add this struct for query parameters
@@ -27,24 +31,6 @@ pub struct UserQuery {
     pub user_id: Option<String>,
 }
 
-/// Helper function to resolve fork_id from request
-fn resolve_fork_id(
-    manager: &crate::services::fork_manager::ForkManager,
-    fork_id: Option<String>,
-    user_id: Option<String>,
-) -> Result<String, StatusCode> {
-    if let Some(fid) = fork_id {
-        Ok(fid)
-    } else if let Some(uid) = user_id {
-        manager
-            .get_user_fork_id(&uid)
-            .cloned()
-            .ok_or(StatusCode::NOT_FOUND)
-    } else {
-        Err(StatusCode::BAD_REQUEST)
-    }
-}
-
 /// Set account balance
 /// Set account balance to EXACT amount
 pub async fn set_balance(
@@ -54,17 +40,18 @@ pub async fn set_balance(
 ) -> Result<Json<SetBalanceResponse>, StatusCode> {
     let address = Pubkey::from_str(&payload.address).map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let mut manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
     let user_id = query.user_id.or(payload.user_id);
-    let fork_id = resolve_fork_id(&manager, payload.fork_id, user_id)?;
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, user_id)?;
 
     let fork = manager
-        .get_fork_mut(&fork_id)
+        .get_fork(&fork_id)
         .ok_or(StatusCode::NOT_FOUND)?;
+    load_accounts(&fork, &[address])
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut fork = fork.write().await;
 
     // Use set_balance to set EXACT amount
     fork.set_balance(&address, payload.lamports)
@@ -88,17 +75,18 @@ pub async fn airdrop(
 
     let lamports = sol_to_lamports(payload.sol);
 
-    let mut manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
     let user_id = query.user_id.or(payload.user_id);
-    let fork_id = resolve_fork_id(&manager, payload.fork_id, user_id)?;
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, user_id)?;
 
     let fork = manager
-        .get_fork_mut(&fork_id)
+        .get_fork(&fork_id)
         .ok_or(StatusCode::NOT_FOUND)?;
+    load_accounts(&fork, &[address])
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut fork = fork.write().await;
 
     // Use add_balance to ADD to existing balance
     fork.add_balance(&address, lamports)
@@ -120,16 +108,17 @@ pub async fn get_balance(
 ) -> Result<Json<GetBalanceResponse>, StatusCode> {
     let address = Pubkey::from_str(&payload.address).map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
     // Prioritize query param over payload
     let user_id = query.user_id.or(payload.user_id);
-    let fork_id = resolve_fork_id(&manager, payload.fork_id, user_id)?;
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, user_id)?;
 
     let fork = manager.get_fork(&fork_id).ok_or(StatusCode::NOT_FOUND)?;
+    load_accounts(&fork, &[address])
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let fork = fork.read().await;
 
     let lamports = fork.get_balance(&address);
     let sol = lamports_to_sol(lamports);
@@ -149,19 +138,92 @@ pub async fn get_account(
 ) -> Result<Json<AccountInfo>, StatusCode> {
     let address = Pubkey::from_str(&payload.address).map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
     // Prioritize query param over payload
     let user_id = query.user_id.or(payload.user_id);
-    let fork_id = resolve_fork_id(&manager, payload.fork_id, user_id)?;
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, user_id)?;
 
     let fork = manager.get_fork(&fork_id).ok_or(StatusCode::NOT_FOUND)?;
+    load_accounts(&fork, &[address])
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let fork = fork.read().await;
 
     fork.get_account_info(&address)
         .map(Json)
         .ok_or(StatusCode::NOT_FOUND)
 }
 
+
+/// Write an account's lamports, owner, flags and data directly
+pub async fn set_account(
+    State(state): State<AppState>,
+    Json(payload): Json<SetAccountRequest>,
+) -> Result<Json<AccountInfo>, (StatusCode, String)> {
+    let address = Pubkey::from_str(&payload.address)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid address".to_string()))?;
+
+    let owner = payload
+        .owner
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid owner".to_string()))?;
+
+    let encoding = payload.encoding.as_deref().unwrap_or("base64");
+    let data = payload
+        .data
+        .as_deref()
+        .map(|data| decode_account_data(data, encoding))
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let patches = payload
+        .patches
+        .unwrap_or_default()
+        .iter()
+        .map(|patch| {
+            Ok(DataPatch {
+                offset: patch.offset,
+                bytes: decode_account_data(&patch.data, encoding)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let manager = &state.fork_manager;
+
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+
+    let fork = manager
+        .get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    load_accounts(&fork, &[address])
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let mut fork = fork.write().await;
+
+    let account = fork
+        .set_account(
+            address,
+            AccountUpdate {
+                lamports: payload.lamports,
+                owner,
+                executable: payload.executable,
+                rent_epoch: payload.rent_epoch,
+                data,
+                patches,
+            },
+        )
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    Ok(Json(AccountInfo {
+        address: payload.address,
+        lamports: account.lamports,
+        owner: account.owner.to_string(),
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data_length: account.data.len(),
+    }))
+}
@@ -0,0 +1,89 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    models::{
+        AdvanceSlotsRequest,
+        ClockResponse,
+        Fork,
+        SetTimestampRequest,
+        WarpEpochRequest,
+        WarpSlotRequest,
+    },
+    state::AppState,
+    utils::resolve_fork_or_user_id,
+};
+
+/// Resolve the fork, apply `change` to it and report the resulting clock
+async fn update_clock(
+    state: &AppState,
+    fork_id: Option<String>,
+    user_id: Option<String>,
+    change: impl FnOnce(&mut Fork) -> Result<(), String>,
+) -> Result<Json<ClockResponse>, (StatusCode, String)> {
+    let manager = &state.fork_manager;
+
+    let fork_id = resolve_fork_or_user_id(manager, fork_id, user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+
+    let fork = manager
+        .get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let mut fork = fork.write().await;
+
+    change(&mut fork).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let clock = fork.clock();
+    Ok(Json(ClockResponse {
+        success: true,
+        fork_id,
+        slot: fork.slot,
+        epoch: clock.epoch,
+        unix_timestamp: clock.unix_timestamp,
+        blockhash: fork.latest_blockhash().to_string(),
+    }))
+}
+
+/// Warp a fork forward to a slot
+pub async fn warp_to_slot(
+    State(state): State<AppState>,
+    Json(payload): Json<WarpSlotRequest>,
+) -> Result<Json<ClockResponse>, (StatusCode, String)> {
+    update_clock(&state, payload.fork_id, payload.user_id, |fork| {
+        fork.warp_to_slot(payload.slot)
+    }).await
+}
+
+/// Advance a fork by a number of slots
+pub async fn advance_slots(
+    State(state): State<AppState>,
+    Json(payload): Json<AdvanceSlotsRequest>,
+) -> Result<Json<ClockResponse>, (StatusCode, String)> {
+    update_clock(&state, payload.fork_id, payload.user_id, |fork| {
+        fork.advance_slots(payload.slots)
+    }).await
+}
+
+/// Warp a fork forward to the first slot of an epoch
+pub async fn warp_to_epoch(
+    State(state): State<AppState>,
+    Json(payload): Json<WarpEpochRequest>,
+) -> Result<Json<ClockResponse>, (StatusCode, String)> {
+    update_clock(&state, payload.fork_id, payload.user_id, |fork| {
+        fork.warp_to_epoch(payload.epoch)
+    }).await
+}
+
+/// Set the unix timestamp programs see through the Clock sysvar
+pub async fn set_unix_timestamp(
+    State(state): State<AppState>,
+    Json(payload): Json<SetTimestampRequest>,
+) -> Result<Json<ClockResponse>, (StatusCode, String)> {
+    update_clock(&state, payload.fork_id, payload.user_id, |fork| {
+        fork.set_unix_timestamp(payload.unix_timestamp);
+        Ok(())
+    }).await
+}
@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+
+use crate::{
+    models::{ForkState, ImportForkRequest, ImportForkResponse},
+    state::AppState,
+};
+
+#[derive(Deserialize)]
+pub struct ExportForkQuery {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+}
+
+/// Export a fork's full state as a shareable JSON document
+pub async fn export_fork(
+    State(state): State<AppState>,
+    Query(params): Query<ExportForkQuery>,
+) -> Result<Json<ForkState>, (StatusCode, String)> {
+    let manager = &state.fork_manager;
+
+    // Determine which fork to export
+    let fork_id = if let Some(fid) = params.fork_id {
+        Some(fid)
+    } else if let Some(uid) = params.user_id {
+        manager.get_user_fork_id(&uid)
+    } else {
+        return Err((StatusCode::BAD_REQUEST, "Missing fork_id or user_id parameter".to_string()));
+    };
+
+    let fork_id = fork_id.ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+
+    manager
+        .export_fork(&fork_id)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::NOT_FOUND, e))
+}
+
+/// Import a previously exported fork as a new fork
+pub async fn import_fork(
+    State(state): State<AppState>,
+    Json(payload): Json<ImportForkRequest>,
+) -> Result<Json<ImportForkResponse>, (StatusCode, String)> {
+    let user_id = payload.user_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let manager = &state.fork_manager;
+
+    let fork_id = manager
+        .import_fork(&payload.state, user_id.clone())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    Ok(Json(ImportForkResponse {
+        success: true,
+        rpc_url: state.rpc_url(&fork_id),
+        fork_id,
+        user_id,
+        slot: payload.state.slot,
+        accounts_loaded: payload.state.accounts.len(),
+    }))
+}
@@ -1,11 +1,11 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     Json,
 };
 
 use crate::{
-    models::{CreateForkRequest, CreateForkResponse, ForkInfo},
+    models::{CloneForkRequest, CloneForkResponse, CreateForkRequest, CreateForkResponse, ForkInfo},
     state::AppState,
 };
 
@@ -13,22 +13,56 @@ use crate::{
 pub async fn create_fork(
     State(state): State<AppState>,
     Json(payload): Json<CreateForkRequest>,
-) -> Result<Json<CreateForkResponse>, StatusCode> {
+) -> Result<Json<CreateForkResponse>, (StatusCode, String)> {
     let user_id = payload.user_id.unwrap_or_else(|| "anonymous".to_string());
 
-    let mut manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
     // Create fork for user
-    let fork_id = manager.create_fork(user_id.clone());
+    let fork_id = manager
+        .create_fork(user_id.clone())
+        .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, e))?;
 
     Ok(Json(CreateForkResponse {
         success: true,
         message: format!("Fork created for user: {}", user_id),
-        fork_id: fork_id.clone(),
-        rpc_url: format!("http://localhost:8899/rpc/fork/{}", fork_id),
+        rpc_url: state.rpc_url(&fork_id),
+        fork_id,
+    }))
+}
+
+/// Clone an existing fork into a new independent fork
+pub async fn clone_fork(
+    State(state): State<AppState>,
+    Path(source_fork_id): Path<String>,
+    Json(payload): Json<CloneForkRequest>,
+) -> Result<Json<CloneForkResponse>, (StatusCode, String)> {
+    let user_id = payload.user_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let manager = &state.fork_manager;
+
+    if manager.get_fork(&source_fork_id).is_none() {
+        return Err((StatusCode::NOT_FOUND, "Fork not found".to_string()));
+    }
+
+    let fork_id = manager
+        .clone_fork(&source_fork_id, user_id.clone())
+        .await
+        .map_err(|e| (StatusCode::CONFLICT, e))?;
+
+    let slot = match manager.get_fork(&fork_id) {
+        Some(fork) => fork.read().await.slot,
+        None => 0,
+    };
+
+    Ok(Json(CloneForkResponse {
+        success: true,
+        message: format!("Fork {} cloned for user: {}", source_fork_id, user_id),
+        rpc_url: state.rpc_url(&fork_id),
+        fork_id,
+        source_fork_id,
+        user_id,
+        slot,
     }))
 }
 
@@ -36,16 +70,14 @@ pub async fn create_fork(
 pub async fn list_forks(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<ForkInfo>>, StatusCode> {
-    let manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
     let fork_ids = manager.get_all_fork_ids();
     let mut fork_infos = Vec::new();
 
     for fork_id in fork_ids {
         if let Some(fork) = manager.get_fork(&fork_id) {
+            let fork = fork.read().await;
             fork_infos.push(fork.get_info());
         }
     }
@@ -57,10 +89,7 @@ pub async fn list_forks(
 pub async fn cleanup_forks(
     State(state): State<AppState>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let mut manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
     let cleaned = manager.cleanup_expired_forks();
 
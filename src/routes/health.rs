@@ -25,12 +25,35 @@ pub async fn root() -> Json<serde_json::Value> {
             "create_fork": "POST /api/v1/forks",
             "list_forks": "GET /api/v1/forks/list",
             "cleanup_forks": "POST /api/v1/forks/cleanup",
+            "clone_fork": "POST /api/v1/forks/{fork_id}/clone",
+            "export_fork": "GET /api/v1/fork/export?fork_id=... or ?user_id=...",
+            "import_fork": "POST /api/v1/forks/import",
             "set_balance": "POST /api/v1/fork/balance/set",
             "get_balance": "POST /api/v1/fork/balance/get",
             "get_account": "POST /api/v1/fork/account",
+            "set_account": "POST /api/v1/fork/account/set",
             "airdrop": "POST /api/v1/fork/airdrop",
+            "warp_to_slot": "POST /api/v1/fork/clock/warp",
+            "advance_slots": "POST /api/v1/fork/clock/advance",
+            "warp_to_epoch": "POST /api/v1/fork/clock/epoch",
+            "set_unix_timestamp": "POST /api/v1/fork/clock/timestamp",
             "send_transaction": "POST /api/v1/fork/transaction/send",
-            "transfer": "POST /api/v1/fork/transfer"
+            "transfer": "POST /api/v1/fork/transfer",
+            "list_transactions": "GET /api/v1/fork/transactions?fork_id=... or ?user_id=...",
+            "impersonate": "POST /api/v1/fork/impersonate",
+            "stop_impersonating": "POST /api/v1/fork/impersonate/stop",
+            "send_impersonated_transaction": "POST /api/v1/fork/transaction/impersonated",
+            "deploy_program": "POST /api/v1/program/deploy",
+            "upgrade_program": "POST /api/v1/program/upgrade",
+            "invoke_program": "POST /api/v1/program/invoke",
+            "load_program": "POST /api/v1/program/load",
+            "program_info": "POST /api/v1/program/info",
+            "account_cache_stats": "GET /api/v1/admin/account-cache",
+            "clear_account_cache": "DELETE /api/v1/admin/account-cache",
+            "rpc": "POST /rpc?fork_id=... or ?user_id=...",
+            "fork_rpc": "POST /rpc/fork/{fork_id}",
+            "user_rpc": "POST /rpc/user/{user_id}",
+            "pubsub": "WebSocket /rpc/fork/{fork_id} or /rpc/user/{user_id} (also on port + 1)"
         }
     }))
 }
@@ -45,24 +68,23 @@ pub async fn get_fork_info(
     State(state): State<AppState>,
     Query(params): Query<ForkInfoQuery>,
 ) -> Result<Json<ForkInfo>, StatusCode> {
-    let manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
     // Determine which fork to get info for
     let fork_id = if let Some(fid) = params.fork_id {
         Some(fid)
     } else if let Some(uid) = params.user_id {
-        manager.get_user_fork_id(&uid).cloned()
+        manager.get_user_fork_id(&uid)
     } else {
         None
     };
 
     let fork_id = fork_id.ok_or(StatusCode::BAD_REQUEST)?;
 
-    manager
+    let fork = manager
         .get_fork(&fork_id)
-        .map(|fork| Json(fork.get_info()))
-        .ok_or(StatusCode::NOT_FOUND)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let fork = fork.read().await;
+
+    Ok(Json(fork.get_info()))
 }
\ No newline at end of file
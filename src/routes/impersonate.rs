@@ -0,0 +1,186 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use solana_sdk::hash::Hash;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_message::{Message, VersionedMessage};
+use solana_pubkey::Pubkey;
+use solana_transaction::versioned::VersionedTransaction;
+use std::str::FromStr;
+
+use crate::{
+    models::{
+        ImpersonateRequest,
+        ImpersonateResponse,
+        InstructionRequest,
+        SendImpersonatedRequest,
+        SendTransactionResponse,
+    },
+    services::load_transaction_accounts,
+    state::AppState,
+    utils::resolve_fork_or_user_id,
+};
+
+/// Treat an account as a valid signer of every transaction on the fork
+pub async fn impersonate_account(
+    State(state): State<AppState>,
+    Json(payload): Json<ImpersonateRequest>,
+) -> Result<Json<ImpersonateResponse>, (StatusCode, String)> {
+    let address = Pubkey::from_str(&payload.address)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid address".to_string()))?;
+
+    let manager = &state.fork_manager;
+
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+
+    let fork = manager
+        .get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let mut fork = fork.write().await;
+
+    fork.impersonate(address);
+
+    Ok(Json(ImpersonateResponse {
+        success: true,
+        fork_id,
+        address: payload.address,
+        impersonated: fork.impersonated_accounts().iter().map(|a| a.to_string()).collect(),
+    }))
+}
+
+/// Require real signatures from an impersonated account again
+pub async fn stop_impersonating(
+    State(state): State<AppState>,
+    Json(payload): Json<ImpersonateRequest>,
+) -> Result<Json<ImpersonateResponse>, (StatusCode, String)> {
+    let address = Pubkey::from_str(&payload.address)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid address".to_string()))?;
+
+    let manager = &state.fork_manager;
+
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+
+    let fork = manager
+        .get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let mut fork = fork.write().await;
+
+    if !fork.stop_impersonating(&address) {
+        return Err((StatusCode::NOT_FOUND, "Account is not impersonated".to_string()));
+    }
+
+    Ok(Json(ImpersonateResponse {
+        success: true,
+        fork_id,
+        address: payload.address,
+        impersonated: fork.impersonated_accounts().iter().map(|a| a.to_string()).collect(),
+    }))
+}
+
+/// Execute an unsigned transaction or instruction list as if every signer had signed it
+pub async fn send_impersonated_transaction(
+    State(state): State<AppState>,
+    Json(payload): Json<SendImpersonatedRequest>,
+) -> Result<Json<SendTransactionResponse>, (StatusCode, String)> {
+    let manager = &state.fork_manager;
+
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+
+    let fork = manager
+        .get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+
+    let recent_blockhash = fork.read().await.latest_blockhash();
+
+    let transaction = match (payload.transaction, payload.instructions) {
+        (Some(encoded), None) => {
+            let bytes = BASE64
+                .decode(&encoded)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid base64: {}", e)))?;
+            let mut transaction: VersionedTransaction = bincode::deserialize(&bytes)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid transaction: {}", e)))?;
+
+            // Unsigned transactions are often built without a blockhash
+            if *transaction.message.recent_blockhash() == Hash::default() {
+                transaction.message.set_recent_blockhash(recent_blockhash);
+            }
+            transaction
+        }
+        (None, Some(instructions)) => {
+            let fee_payer = payload
+                .fee_payer
+                .ok_or((StatusCode::BAD_REQUEST, "fee_payer is required with instructions".to_string()))?;
+            let fee_payer = Pubkey::from_str(&fee_payer)
+                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid fee_payer".to_string()))?;
+
+            let instructions = instructions
+                .iter()
+                .map(parse_instruction)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let message = Message::new_with_blockhash(&instructions, Some(&fee_payer), &recent_blockhash);
+            VersionedTransaction {
+                signatures: Vec::new(),
+                message: VersionedMessage::Legacy(message),
+            }
+        }
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Provide either transaction or instructions".to_string(),
+            ));
+        }
+    };
+
+    load_transaction_accounts(&fork, &transaction)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let result = fork
+        .write()
+        .await
+        .send_impersonated_transaction(transaction, payload.include_diff.unwrap_or(false))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(SendTransactionResponse {
+        success: result.success,
+        signature: result.signature,
+        error: result.error,
+        account_diffs: result.account_diffs,
+    }))
+}
+
+fn parse_instruction(instruction: &InstructionRequest) -> Result<Instruction, (StatusCode, String)> {
+    let program_id = Pubkey::from_str(&instruction.program_id)
+        .map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid program_id: {}", instruction.program_id)))?;
+
+    let accounts = instruction
+        .accounts
+        .iter()
+        .map(|meta| {
+            let pubkey = Pubkey::from_str(&meta.pubkey)
+                .map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid account: {}", meta.pubkey)))?;
+            Ok(AccountMeta {
+                pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+        })
+        .collect::<Result<Vec<_>, (StatusCode, String)>>()?;
+
+    let data = BASE64
+        .decode(&instruction.data)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid instruction data: {}", e)))?;
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
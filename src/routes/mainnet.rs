@@ -19,72 +19,88 @@ use crate::{
             LoadAccountsResponse,
         },
     },
-    services::MainnetClient,
+    services::{MainnetClient, SharedFork},
     state::AppState,
     utils::resolve_fork_id,
 };
 
+/// Fetch `addresses` from mainnet in batches and write the ones that exist
+/// into the fork, which is only locked once everything has been fetched.
+/// Returns the addresses that were loaded.
+async fn load_into_fork(
+    mainnet_client: &MainnetClient,
+    fork: &SharedFork,
+    addresses: &[String],
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let accounts = mainnet_client.fetch_accounts(addresses).await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let mut fork = fork.write().await;
+
+    let mut loaded_addresses = Vec::new();
+
+    for (address, account) in accounts {
+        let Some(account) = account else {
+            eprintln!("Warning: Failed to load account {}: not found on mainnet", address);
+            continue;
+        };
+
+        let pubkey = Pubkey::from_str(&address)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid pubkey {}: {}", address, e)))?;
+
+        fork.svm.set_account(pubkey, account)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to set account: {:?}", e)))?;
+        fork.notify_accounts(&[pubkey]);
+
+        loaded_addresses.push(address);
+    }
+
+    Ok(loaded_addresses)
+}
+
 /// Create a new fork and load accounts from mainnet
 pub async fn create_mainnet_fork(
     State(state): State<AppState>,
     Json(payload): Json<CreateMainnetForkRequest>,
 ) -> Result<Json<CreateMainnetForkResponse>, (StatusCode, String)> {
     // Create the mainnet client
-    let mainnet_client = if let Some(ref endpoint) = payload.rpc_endpoint {
-        MainnetClient::with_endpoint(endpoint)
-    } else {
-        MainnetClient::new()
-    };
+    let mainnet_client = state
+        .mainnet_client(payload.rpc_endpoint.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
     let user_id = payload.user_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let lazy_load = payload.lazy_load.unwrap_or(false);
 
     // Create fork with mainnet sync
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
-    let fork_id = fork_manager.create_fork_with_mainnet_sync(user_id.clone(), &mainnet_client)
+    let fork_id = fork_manager.create_fork_with_mainnet_sync(user_id.clone(), &mainnet_client, lazy_load)
+        .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to sync with mainnet: {}", e)))?;
 
     // Get fork metadata
     let fork = fork_manager.get_fork(&fork_id)
         .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Fork creation failed".to_string()))?;
     
-    let created_at = fork.created_at;
-    let mainnet_slot = fork.mainnet_slot;
-    let mainnet_blockhash = fork.mainnet_blockhash.clone();
+    let (created_at, mainnet_slot, mainnet_blockhash, lazy_load) = {
+        let fork = fork.read().await;
+        (fork.created_at, fork.mainnet_slot, fork.mainnet_blockhash.clone(), fork.is_lazy())
+    };
 
-    // Load accounts from mainnet into the fork
-    let mut loaded_addresses = Vec::new();
-    
-    for address in &payload.accounts {
-        match mainnet_client.fetch_account(address) {
-            Ok(account) => {
-                // Parse the pubkey
-                let pubkey = solana_pubkey::Pubkey::from_str(address)
-                    .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid pubkey {}: {}", address, e)))?;
-
-                // Get mutable fork reference
-                let fork = fork_manager.get_fork_mut(&fork_id)
-                    .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
-
-                // Set the account in the fork
-                fork.svm.set_account(pubkey, account)
-                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to set account: {:?}", e)))?;
-
-                loaded_addresses.push(address.clone());
-            }
-            Err(e) => {
-                eprintln!("Warning: Failed to load account {}: {}", address, e);
-            }
+    // Load accounts from mainnet into the fork. The fork exists by now, so
+    // a failed fetch only leaves it without the accounts.
+    let loaded_addresses = match load_into_fork(&mainnet_client, &fork, &payload.accounts).await {
+        Ok(loaded_addresses) => loaded_addresses,
+        Err((_, e)) => {
+            eprintln!("Warning: Failed to load accounts: {}", e);
+            Vec::new()
         }
-    }
-
-    // Calculate expires_at (15 minutes from creation)
-    let expires_at = created_at + (15 * 60);
+    };
 
-    drop(fork_manager);
+    let expires_at = created_at + state.config.fork_lifetime.as_secs();
 
    Ok(Json(CreateMainnetForkResponse {
+    rpc_url: state.rpc_url(&fork_id),
     fork_id,
     user_id,
     created_at: format!("{}", created_at),
@@ -93,6 +109,7 @@ pub async fn create_mainnet_fork(
     mainnet_blockhash,      // ← NEW
     accounts_loaded: loaded_addresses.len(),
     loaded_addresses,
+    lazy_load,
 }))
 }
 /// Load a single account from mainnet into an existing fork
@@ -100,31 +117,31 @@ pub async fn load_account(
     State(state): State<AppState>,
     Json(payload): Json<LoadAccountRequest>,
 ) -> Result<Json<LoadAccountsResponse>, (StatusCode, String)> {
-    let mainnet_client = if let Some(ref endpoint) = payload.rpc_endpoint {
-        MainnetClient::with_endpoint(endpoint)
-    } else {
-        MainnetClient::new()
-    };
+    let mainnet_client = state
+        .mainnet_client(payload.rpc_endpoint.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
         .map_err(|e| (e, "Fork not found".to_string()))?;
 
-    let fork = fork_manager.get_fork_mut(&fork_id)
+    let fork = fork_manager.get_fork(&fork_id)
         .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
 
-    // Fetch account from mainnet
+    // Fetch account from mainnet before locking the fork
     let account = mainnet_client.fetch_account(&payload.address)
+        .await
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
     // Load into fork
     let pubkey = Pubkey::from_str(&payload.address)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid pubkey: {}", e)))?;
 
+    let mut fork = fork.write().await;
     fork.svm.set_account(pubkey, account)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to set account: {:?}", e)))?;
+    fork.notify_accounts(&[pubkey]);
 
     Ok(Json(LoadAccountsResponse {
         success: true,
@@ -138,39 +155,19 @@ pub async fn load_accounts(
     State(state): State<AppState>,
     Json(payload): Json<LoadAccountsRequest>,
 ) -> Result<Json<LoadAccountsResponse>, (StatusCode, String)> {
-    let mainnet_client = if let Some(ref endpoint) = payload.rpc_endpoint {
-        MainnetClient::with_endpoint(endpoint)
-    } else {
-        MainnetClient::new()
-    };
+    let mainnet_client = state
+        .mainnet_client(payload.rpc_endpoint.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
         .map_err(|e| (e, "Fork not found".to_string()))?;
 
-    let fork = fork_manager.get_fork_mut(&fork_id)
+    let fork = fork_manager.get_fork(&fork_id)
         .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
 
-    let mut loaded_addresses = Vec::new();
-
-    for address in &payload.addresses {
-        match mainnet_client.fetch_account(address) {
-            Ok(account) => {
-                let pubkey = Pubkey::from_str(address)
-                    .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid pubkey {}: {}", address, e)))?;
-
-                fork.svm.set_account(pubkey, account)
-                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to set account: {:?}", e)))?;
-
-                loaded_addresses.push(address.clone());
-            }
-            Err(e) => {
-                eprintln!("Warning: Failed to load account {}: {}", address, e);
-            }
-        }
-    }
+    let loaded_addresses = load_into_fork(&mainnet_client, &fork, &payload.addresses).await?;
 
     Ok(Json(LoadAccountsResponse {
         success: true,
@@ -184,25 +181,25 @@ pub async fn load_token_accounts(
     State(state): State<AppState>,
     Json(payload): Json<LoadTokenAccountsRequest>,
 ) -> Result<Json<LoadAccountsResponse>, (StatusCode, String)> {
-    let mainnet_client = if let Some(ref endpoint) = payload.rpc_endpoint {
-        MainnetClient::with_endpoint(endpoint)
-    } else {
-        MainnetClient::new()
-    };
+    let mainnet_client = state
+        .mainnet_client(payload.rpc_endpoint.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
         .map_err(|e| (e, "Fork not found".to_string()))?;
 
-    let fork = fork_manager.get_fork_mut(&fork_id)
+    let fork = fork_manager.get_fork(&fork_id)
         .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
 
-    // Fetch token accounts from mainnet
+    // Fetch token accounts from mainnet before locking the fork
     let token_accounts = mainnet_client.fetch_token_accounts(&payload.owner)
+        .await
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
+    let mut fork = fork.write().await;
+
     let mut loaded_addresses = Vec::new();
 
     for (address, account) in token_accounts {
@@ -211,6 +208,7 @@ pub async fn load_token_accounts(
 
         fork.svm.set_account(pubkey, account)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to set account: {:?}", e)))?;
+        fork.notify_accounts(&[pubkey]);
 
         loaded_addresses.push(address);
     }
@@ -1,20 +1,33 @@
+pub mod admin;
 pub mod balance;
+pub mod clock;
+pub mod export;
 pub mod fork;
 pub mod health;
+pub mod impersonate;
 pub mod transaction;
 pub mod token;
 pub mod mainnet;
 pub mod program;
+pub mod pubsub;
 pub mod rpc;
+pub mod snapshot;
 
 // Re-export all route handlers
-pub use balance::{airdrop, get_account, get_balance, set_balance};
-pub use fork::{cleanup_forks, create_fork, list_forks};
+pub use admin::{clear_account_cache, get_account_cache_stats};
+pub use balance::{airdrop, get_account, get_balance, set_account, set_balance};
+pub use clock::{advance_slots, set_unix_timestamp, warp_to_epoch, warp_to_slot};
+pub use export::{export_fork, import_fork};
+pub use fork::{cleanup_forks, clone_fork, create_fork, list_forks};
 pub use health::{get_fork_info, health_check, root};
-pub use transaction::{send_transaction, transfer_sol};
+pub use impersonate::{impersonate_account, send_impersonated_transaction, stop_impersonating};
+pub use transaction::{list_transactions, send_transaction, transfer_sol};
 pub use token::{
-    create_token_account, create_token_mint, get_token_balance, mint_tokens, transfer_tokens,
+    create_token_account, create_token_mint, get_token_balance, mint_tokens, set_token_balance,
+    transfer_tokens,
 };
 pub use mainnet::*;
 pub use program::*;
-pub use rpc::*;
\ No newline at end of file
+pub use pubsub::{handle_fork_pubsub, handle_pubsub, handle_user_pubsub};
+pub use rpc::*;
+pub use snapshot::{create_snapshot, revert_snapshot};
\ No newline at end of file
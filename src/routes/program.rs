@@ -6,13 +6,17 @@ use solana_keypair::Keypair;
 use solana_signer::Signer;
 use solana_transaction::versioned::VersionedTransaction;
 use solana_message::{Message, VersionedMessage};
-use solana_instruction::{AccountMeta, Instruction};
+use solana_instruction::{error::InstructionError, AccountMeta, Instruction};
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_sdk::transaction::TransactionError;
 use std::str::FromStr;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
+use crate::constants::{BPF_LOADER_ID, BPF_LOADER_UPGRADEABLE_ID};
 use crate::models::program::*;
+use crate::services::load_accounts;
 use crate::state::AppState;
-use crate::services::MainnetClient;
+use crate::utils::get_upgradeable_loader_state;
 
 /// Helper function to resolve fork_id from user_id
 fn resolve_fork_id(
@@ -21,13 +25,13 @@ fn resolve_fork_id(
 ) -> Result<String, StatusCode> {
     manager
         .get_user_fork_id(user_id)
-        .cloned()
         .ok_or(StatusCode::NOT_FOUND)
 }
 
 /// Helper function to parse base58 keypair
 fn parse_keypair(keypair_str: &str) -> Result<Keypair, String> {
-    Ok(Keypair::from_base58_string(keypair_str))
+    let bytes = bs58::decode(keypair_str).into_vec().map_err(|e| e.to_string())?;
+    Keypair::try_from(&bytes[..]).map_err(|e| e.to_string())
 }
 
 /// Deploy a program to the fork
@@ -35,14 +39,7 @@ pub async fn deploy_program(
     State(state): State<AppState>,
     Json(payload): Json<DeployProgramRequest>,
 ) -> Result<Json<DeployProgramResponse>, (StatusCode, String)> {
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
-    
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
-        .map_err(|e| (e, "Fork not found".to_string()))?;
-    
-    let fork = fork_manager.get_fork_mut(&fork_id)
-        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
     // Parse program keypair
     let program_keypair = parse_keypair(&payload.program_keypair)
@@ -52,14 +49,24 @@ pub async fn deploy_program(
     let program_data = BASE64.decode(&payload.program_data)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid base64 program data: {}", e)))?;
 
+    // The payer becomes the upgrade authority, like `solana program deploy`
+    let payer = parse_keypair(&payload.payer_keypair)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid payer keypair: {}", e)))?;
+    
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+    
+    let fork = fork_manager.get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let mut fork = fork.write().await;
+
     let program_id = program_keypair.pubkey();
     let program_size = program_data.len();
 
-    // Use liteSVM's add_program method to deploy
-    // This directly adds the program without needing deployment transactions
-    // FIXED: Properly handle the Result returned by add_program
-    fork.svm.add_program(program_id, &program_data)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to deploy program: {:?}", e)))?;
+    // Install the program and its ProgramData account directly,
+    // without needing deployment transactions
+    fork.deploy_upgradeable_program(program_id, &program_data, Some(payer.pubkey()))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to deploy program: {}", e)))?;
 
     Ok(Json(DeployProgramResponse {
         program_id: program_id.to_string(),
@@ -69,20 +76,84 @@ pub async fn deploy_program(
     }))
 }
 
-/// Invoke a program instruction
-pub async fn invoke_program(
+/// Upgrade an upgradeable program in place, replacing its bytes
+pub async fn upgrade_program(
     State(state): State<AppState>,
-    Json(payload): Json<InvokeProgramRequest>,
-) -> Result<Json<InvokeProgramResponse>, (StatusCode, String)> {
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
+    Json(payload): Json<UpgradeProgramRequest>,
+) -> Result<Json<UpgradeProgramResponse>, (StatusCode, String)> {
+    let fork_manager = &state.fork_manager;
+
+    let program_id = Pubkey::from_str(&payload.program_id)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid program ID: {}", e)))?;
+
+    let upgrade_authority = parse_keypair(&payload.upgrade_authority_keypair)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid upgrade authority keypair: {}", e)))?;
+
+    let new_program_data = BASE64.decode(&payload.new_program_data)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid base64 program data: {}", e)))?;
     
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
         .map_err(|e| (e, "Fork not found".to_string()))?;
     
-    let fork = fork_manager.get_fork_mut(&fork_id)
+    let fork = fork_manager.get_fork(&fork_id)
         .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
 
+    // Lazy loading brings in the ProgramData account along with the program
+    load_accounts(&fork, &[program_id])
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let mut fork = fork.write().await;
+
+    let program_account = fork.get_account(&program_id)
+        .ok_or((StatusCode::NOT_FOUND, "Program not found in fork".to_string()))?;
+
+    let Some(UpgradeableLoaderState::Program { programdata_address }) =
+        get_upgradeable_loader_state(&program_account)
+    else {
+        return Err((StatusCode::BAD_REQUEST, "Program is not owned by the BPF Upgradeable Loader".to_string()));
+    };
+
+    let programdata_account = fork.get_account(&programdata_address)
+        .ok_or((StatusCode::NOT_FOUND, "Program data account not found in fork".to_string()))?;
+
+    let Some(UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. }) =
+        get_upgradeable_loader_state(&programdata_account)
+    else {
+        return Err((StatusCode::BAD_REQUEST, "Invalid program data account".to_string()));
+    };
+
+    // Only the current upgrade authority may replace the program
+    match upgrade_authority_address {
+        None => {
+            return Err((StatusCode::FORBIDDEN, "Program is immutable".to_string()));
+        }
+        Some(authority) if authority != upgrade_authority.pubkey() => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                format!("Upgrade authority mismatch: expected {}", authority),
+            ));
+        }
+        Some(_) => {}
+    }
+
+    fork.deploy_upgradeable_program(program_id, &new_program_data, upgrade_authority_address)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to upgrade program: {}", e)))?;
+
+    Ok(Json(UpgradeProgramResponse {
+        program_id: payload.program_id,
+        success: true,
+        program_size: new_program_data.len(),
+        upgrade_authority: upgrade_authority.pubkey().to_string(),
+    }))
+}
+
+/// Invoke a program instruction
+pub async fn invoke_program(
+    State(state): State<AppState>,
+    Json(payload): Json<InvokeProgramRequest>,
+) -> Result<Json<InvokeProgramResponse>, (StatusCode, String)> {
+    let fork_manager = &state.fork_manager;
+
     // Parse program ID
     let program_id = Pubkey::from_str(&payload.program_id)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid program ID: {}", e)))?;
@@ -115,15 +186,29 @@ pub async fn invoke_program(
         .collect::<Result<Vec<_>, String>>()
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
+    let mut addresses: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+    addresses.push(program_id);
+    addresses.extend(accounts.iter().map(|meta| meta.pubkey));
+
     // Create instruction
     let instruction = Instruction {
         program_id,
         accounts,
         data: instruction_data,
     };
+    
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+    
+    let fork = fork_manager.get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    load_accounts(&fork, &addresses)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let mut fork = fork.write().await;
 
     // Get recent blockhash
-    let blockhash = fork.svm.latest_blockhash();
+    let blockhash = fork.latest_blockhash();
 
     // Create and sign transaction
     let msg = Message::new_with_blockhash(
@@ -137,50 +222,88 @@ pub async fn invoke_program(
     let tx = VersionedTransaction::try_new(versioned_msg, &signer_refs)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create transaction: {:?}", e)))?;
 
+    // Account keys resolve the indexes used by inner instructions
+    let account_keys = tx.message.static_account_keys().to_vec();
+    let signature = tx.signatures[0].to_string();
+
     // Send transaction
-    let result = fork.svm.send_transaction(tx);
-
-    match result {
-        Ok(_meta) => {
-            // TODO: Extract logs from transaction metadata if available
-            Ok(Json(InvokeProgramResponse {
-                signature: "instruction_executed".to_string(),
-                success: true,
-                logs: vec!["Program executed successfully".to_string()],
-                error: None,
-            }))
-        },
-        Err(e) => {
-            Ok(Json(InvokeProgramResponse {
-                signature: "execution_failed".to_string(),
-                success: false,
-                logs: vec![],
-                error: Some(format!("{:?}", e)),
-            }))
-        }
-    }
+    let result = fork.execute_transaction(tx)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let (meta, err) = match result {
+        Ok(meta) => (meta, None),
+        Err(failed) => (failed.meta, Some(failed.err)),
+    };
+
+    let return_data = (!meta.return_data.data.is_empty()).then(|| ProgramReturnData {
+        program_id: meta.return_data.program_id.to_string(),
+        data: BASE64.encode(&meta.return_data.data),
+    });
+
+    let key_at = |index: u8| {
+        account_keys
+            .get(index as usize)
+            .map(|key| key.to_string())
+            .unwrap_or_default()
+    };
+    let inner_instructions = meta.inner_instructions
+        .iter()
+        .enumerate()
+        .flat_map(|(instruction_index, inner)| {
+            inner.iter().map(move |inner| (instruction_index, inner))
+        })
+        .map(|(instruction_index, inner)| InnerInstructionData {
+            instruction_index,
+            stack_height: inner.stack_height,
+            program_id: key_at(inner.instruction.program_id_index),
+            accounts: inner.instruction.accounts.iter().map(|index| key_at(*index)).collect(),
+            data: BASE64.encode(&inner.instruction.data),
+        })
+        .collect();
+
+    let (failed_instruction_index, instruction_error, custom_error_code) = match &err {
+        Some(TransactionError::InstructionError(index, instruction_error)) => (
+            Some(*index),
+            Some(instruction_error.to_string()),
+            match instruction_error {
+                InstructionError::Custom(code) => Some(*code),
+                _ => None,
+            },
+        ),
+        _ => (None, None, None),
+    };
+
+    Ok(Json(InvokeProgramResponse {
+        signature,
+        success: err.is_none(),
+        logs: meta.logs,
+        error: err.map(|e| e.to_string()),
+        compute_units_consumed: meta.compute_units_consumed,
+        return_data,
+        inner_instructions,
+        failed_instruction_index,
+        instruction_error,
+        custom_error_code,
+    }))
 }
 
-//// Load a program from mainnet (SIMPLIFIED VERSION)
+/// Load a program from mainnet (SIMPLIFIED VERSION)
 pub async fn load_program(
     State(state): State<AppState>,
     Json(payload): Json<LoadProgramRequest>,
 ) -> Result<Json<LoadProgramResponse>, (StatusCode, String)> {
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
+    let fork_manager = &state.fork_manager;
     
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
         .map_err(|e| (e, "Fork not found".to_string()))?;
     
-    let fork = fork_manager.get_fork_mut(&fork_id)
+    let fork = fork_manager.get_fork(&fork_id)
         .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
 
     // Create mainnet client
-    let mainnet_client = if let Some(ref endpoint) = payload.rpc_endpoint {
-        MainnetClient::with_endpoint(endpoint)
-    } else {
-        MainnetClient::new()
-    };
+    let mainnet_client = state
+        .mainnet_client(payload.rpc_endpoint.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
     // Parse program ID
     let program_id = Pubkey::from_str(&payload.program_id)
@@ -188,43 +311,79 @@ pub async fn load_program(
 
     // Fetch program account from mainnet
     let program_account = mainnet_client.fetch_account(&payload.program_id)
+        .await
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to fetch program: {}", e)))?;
 
     let program_size = program_account.data.len();
     let is_executable = program_account.executable;
 
+    // Upgradeable programs keep their executable data in a separate ProgramData
+    // account, fetched here so mainnet is not queried while the fork is locked
+    let programdata = match get_upgradeable_loader_state(&program_account) {
+        Some(UpgradeableLoaderState::Program { programdata_address }) if is_executable => {
+            let programdata_account = mainnet_client.fetch_account(&programdata_address.to_string())
+                .await
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to fetch program data: {}", e)))?;
+            Some((programdata_address, programdata_account))
+        }
+        _ => None,
+    };
+
+    let mut fork = fork.write().await;
+
     // For NON-EXECUTABLE accounts, just use set_account
     if !is_executable {
         fork.svm.set_account(program_id, program_account)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load account: {:?}", e)))?;
+        fork.notify_accounts(&[program_id]);
         
         return Ok(Json(LoadProgramResponse {
             program_id: payload.program_id,
             success: true,
             program_size,
             is_executable,
+            programdata_address: None,
+            upgrade_authority: None,
         }));
     }
 
     // For EXECUTABLE programs, try different approaches based on owner
-    let bpf_loader_v2 = Pubkey::from_str("BPFLoader2111111111111111111111111111111111").unwrap();
-    let bpf_loader_v3 = Pubkey::from_str("BPFLoaderUpgradeab1e11111111111111111111111").unwrap();
-
-    if program_account.owner == bpf_loader_v2 {
+    if program_account.owner == BPF_LOADER_ID {
         // BPF Loader v2 - executable data is in the program account itself
         fork.svm.add_program(program_id, &program_account.data)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load BPF v2 program: {:?}", e)))?;
-    } else if program_account.owner == bpf_loader_v3 {
-        // BPF Loader v3 (Upgradeable) - this is complex
-        // For now, just return an error explaining the limitation
-        return Err((
-            StatusCode::NOT_IMPLEMENTED,
-            "Upgradeable programs (BPF Loader v3) are not fully supported yet. Try loading simpler BPF v2 programs like SPL Token.".to_string()
-        ));
+        fork.notify_accounts(&[program_id]);
+    } else if program_account.owner == BPF_LOADER_UPGRADEABLE_ID {
+        // BPF Loader v3 (Upgradeable) - executable data lives in a separate ProgramData account
+        let Some((programdata_address, programdata_account)) = programdata else {
+            return Err((StatusCode::BAD_REQUEST, "Invalid upgradeable program account".to_string()));
+        };
+
+        let Some(UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. }) =
+            get_upgradeable_loader_state(&programdata_account)
+        else {
+            return Err((StatusCode::BAD_REQUEST, "Invalid program data account".to_string()));
+        };
+
+        let program_size = programdata_account.data.len()
+            .saturating_sub(UpgradeableLoaderState::size_of_programdata_metadata());
+
+        fork.add_upgradeable_program(program_id, program_account, programdata_address, programdata_account)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load BPF v3 program: {}", e)))?;
+
+        return Ok(Json(LoadProgramResponse {
+            program_id: payload.program_id,
+            success: true,
+            program_size,
+            is_executable,
+            programdata_address: Some(programdata_address.to_string()),
+            upgrade_authority: upgrade_authority_address.map(|a| a.to_string()),
+        }));
     } else {
         // Unknown loader, try set_account
         fork.svm.set_account(program_id, program_account)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load program: {:?}", e)))?;
+        fork.notify_accounts(&[program_id]);
     }
 
     Ok(Json(LoadProgramResponse {
@@ -232,6 +391,8 @@ pub async fn load_program(
         success: true,
         program_size,
         is_executable,
+        programdata_address: None,
+        upgrade_authority: None,
     }))
 }
 
@@ -240,14 +401,14 @@ pub async fn get_program_info(
     State(state): State<AppState>,
     Json(payload): Json<GetProgramRequest>,
 ) -> Result<Json<ProgramInfo>, (StatusCode, String)> {
-    let fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
+    let fork_manager = &state.fork_manager;
     
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
         .map_err(|e| (e, "Fork not found".to_string()))?;
     
     let fork = fork_manager.get_fork(&fork_id)
         .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let fork = fork.read().await;
 
     let program_id = Pubkey::from_str(&payload.program_id)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid program ID: {}", e)))?;
@@ -255,11 +416,28 @@ pub async fn get_program_info(
     let account = fork.svm.get_account(&program_id)
         .ok_or((StatusCode::NOT_FOUND, "Program not found in fork".to_string()))?;
 
+    // Upgradeable programs keep their upgrade authority in the ProgramData account
+    let (programdata_address, upgrade_authority) = match get_upgradeable_loader_state(&account) {
+        Some(UpgradeableLoaderState::Program { programdata_address }) => {
+            let upgrade_authority = fork.svm.get_account(&programdata_address)
+                .and_then(|programdata| match get_upgradeable_loader_state(&programdata) {
+                    Some(UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. }) => {
+                        upgrade_authority_address.map(|a| a.to_string())
+                    }
+                    _ => None,
+                });
+            (Some(programdata_address.to_string()), upgrade_authority)
+        }
+        _ => (None, None),
+    };
+
     Ok(Json(ProgramInfo {
         program_id: payload.program_id,
         executable: account.executable,
         owner: account.owner.to_string(),
         data_size: account.data.len(),
         lamports: account.lamports,
+        programdata_address,
+        upgrade_authority,
     }))
 }
\ No newline at end of file
@@ -0,0 +1,521 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::Response,
+};
+use base64::{Engine as _, engine::general_purpose};
+use serde_json::{json, Value};
+use solana_account::Account;
+use solana_pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    models::{ForkEvent, RpcError, RpcNotification, RpcRequest, RpcResponse},
+    routes::rpc::RpcQuery,
+    state::AppState,
+    utils::account_to_json,
+};
+
+/// A live subscription on a PubSub connection
+enum Subscription {
+    Account { pubkey: Pubkey, encoding: String },
+    Program { program_id: Pubkey, encoding: String, filters: Vec<AccountFilter> },
+    Signature { signature: String },
+    /// `mentions: None` matches every transaction
+    Logs { mentions: Option<Pubkey> },
+    Slot,
+}
+
+/// `programSubscribe` filter, same semantics as `getProgramAccounts`
+enum AccountFilter {
+    DataSize(usize),
+    Memcmp { offset: usize, bytes: Vec<u8> },
+}
+
+impl AccountFilter {
+    fn matches(&self, account: &Account) -> bool {
+        match self {
+            AccountFilter::DataSize(size) => account.data.len() == *size,
+            AccountFilter::Memcmp { offset, bytes } => account
+                .data
+                .get(*offset..offset + bytes.len())
+                .is_some_and(|data| data == bytes.as_slice()),
+        }
+    }
+}
+
+/// Subscriptions of one connection, keyed by subscription ID
+struct Subscriptions {
+    active: HashMap<u64, Subscription>,
+    next_id: u64,
+}
+
+/// PubSub endpoint - WebSocket on `/rpc?fork_id=...` or `/rpc?user_id=...`
+pub async fn handle_pubsub(
+    State(state): State<AppState>,
+    Query(query): Query<RpcQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let fork_id = if let Some(fid) = query.fork_id {
+        fid
+    } else if let Some(uid) = query.user_id {
+        resolve_user_fork(&state, &uid)?
+    } else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    upgrade(state, fork_id, ws).await
+}
+
+/// Per-fork PubSub endpoint - WebSocket on `/rpc/fork/{fork_id}`
+pub async fn handle_fork_pubsub(
+    State(state): State<AppState>,
+    Path(fork_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    upgrade(state, fork_id, ws).await
+}
+
+/// Per-user PubSub endpoint - WebSocket on `/rpc/user/{user_id}`
+pub async fn handle_user_pubsub(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let fork_id = resolve_user_fork(&state, &user_id)?;
+    upgrade(state, fork_id, ws).await
+}
+
+fn resolve_user_fork(state: &AppState, user_id: &str) -> Result<String, StatusCode> {
+    state
+        .fork_manager
+        .get_user_fork_id(user_id)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Subscribe to the fork's events and hand the connection over to `serve_pubsub`
+async fn upgrade(state: AppState, fork_id: String, ws: WebSocketUpgrade) -> Result<Response, StatusCode> {
+    let events = state
+        .fork_manager
+        .get_fork(&fork_id)
+        .ok_or(StatusCode::NOT_FOUND)?
+        .read()
+        .await
+        .subscribe();
+
+    Ok(ws.on_upgrade(move |socket| serve_pubsub(socket, state, fork_id, events)))
+}
+
+/// Answer subscription requests and push notifications until the client
+/// disconnects or the fork is deleted
+async fn serve_pubsub(
+    mut socket: WebSocket,
+    state: AppState,
+    fork_id: String,
+    mut events: broadcast::Receiver<ForkEvent>,
+) {
+    let mut subscriptions = Subscriptions {
+        active: HashMap::new(),
+        next_id: 0,
+    };
+
+    loop {
+        let outgoing = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle_message(&state, &fork_id, &mut subscriptions, text.as_str()).await
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum; binary frames are not part of the protocol
+                Some(Ok(_)) => continue,
+            },
+            event = events.recv() => match event {
+                Ok(event) => notifications_for(&mut subscriptions, &event),
+                Err(RecvError::Lagged(skipped)) => {
+                    eprintln!("Warning: PubSub client on {} missed {} event(s)", fork_id, skipped);
+                    missed_signature_notifications(&state, &fork_id, &mut subscriptions).await
+                }
+                // The fork expired or was deleted
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        for message in outgoing {
+            if socket.send(Message::Text(message.into())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Handle one client message, returning the messages to send back
+async fn handle_message(
+    state: &AppState,
+    fork_id: &str,
+    subscriptions: &mut Subscriptions,
+    text: &str,
+) -> Vec<String> {
+    let payload: Value = match serde_json::from_str(text) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return vec![to_text(&RpcResponse::error(
+                Value::Null,
+                -32700,
+                format!("Parse error: {}", e),
+            ))];
+        }
+    };
+
+    let request: RpcRequest = match serde_json::from_value(payload) {
+        Ok(request) => request,
+        Err(e) => {
+            return vec![to_text(&RpcResponse::error(
+                Value::Null,
+                -32600,
+                format!("Invalid request: {}", e),
+            ))];
+        }
+    };
+
+    let params = request
+        .params
+        .map(|params| params.into_positional(param_names(&request.method)))
+        .unwrap_or_default();
+
+    let mut outgoing = Vec::new();
+    let result = match request.method.as_str() {
+        "accountSubscribe" => subscribe_account(&params).map(|s| subscriptions.add(s)),
+        "programSubscribe" => subscribe_program(&params).map(|s| subscriptions.add(s)),
+        "logsSubscribe" => subscribe_logs(&params).map(|s| subscriptions.add(s)),
+        "slotSubscribe" => Ok(subscriptions.add(Subscription::Slot)),
+        "signatureSubscribe" => {
+            subscribe_signature(state, fork_id, subscriptions, &params, &mut outgoing).await
+        }
+        "accountUnsubscribe" | "programUnsubscribe" | "logsUnsubscribe" | "slotUnsubscribe"
+        | "signatureUnsubscribe" => unsubscribe(subscriptions, &params),
+        method => Err(RpcError::new(-32601, format!("Method not supported: {}", method))),
+    };
+
+    let response = match result {
+        Ok(value) => RpcResponse::success(request.id, value),
+        Err(error) => RpcResponse::from_error(request.id, error),
+    };
+
+    // The subscription ID must reach the client before any notification for it
+    outgoing.insert(0, to_text(&response));
+    outgoing
+}
+
+/// Names of the positional parameters of PubSub methods, for named params
+fn param_names(method: &str) -> &'static [&'static str] {
+    match method {
+        "accountSubscribe" => &["pubkey", "config"],
+        "programSubscribe" => &["programId", "config"],
+        "signatureSubscribe" => &["signature", "config"],
+        "logsSubscribe" => &["filter", "config"],
+        method if method.ends_with("Unsubscribe") => &["subscription"],
+        _ => &[],
+    }
+}
+
+impl Subscriptions {
+    fn add(&mut self, subscription: Subscription) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.active.insert(id, subscription);
+        json!(id)
+    }
+}
+
+fn parse_pubkey(value: Option<&Value>, name: &str) -> Result<Pubkey, RpcError> {
+    let value = value
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, format!("Missing {} parameter", name)))?;
+
+    Pubkey::from_str(value).map_err(|e| RpcError::new(-32602, format!("Invalid {}: {}", name, e)))
+}
+
+fn config_encoding(params: &[Value]) -> String {
+    params
+        .get(1)
+        .and_then(|c| c.get("encoding"))
+        .and_then(|e| e.as_str())
+        .unwrap_or("base64")
+        .to_string()
+}
+
+fn subscribe_account(params: &[Value]) -> Result<Subscription, RpcError> {
+    Ok(Subscription::Account {
+        pubkey: parse_pubkey(params.first(), "pubkey")?,
+        encoding: config_encoding(params),
+    })
+}
+
+fn subscribe_program(params: &[Value]) -> Result<Subscription, RpcError> {
+    let program_id = parse_pubkey(params.first(), "program ID")?;
+
+    let filters = params
+        .get(1)
+        .and_then(|c| c.get("filters"))
+        .and_then(|f| f.as_array())
+        .map(|filters| filters.iter().map(parse_filter).collect::<Result<Vec<_>, _>>())
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(Subscription::Program {
+        program_id,
+        encoding: config_encoding(params),
+        filters,
+    })
+}
+
+fn parse_filter(filter: &Value) -> Result<AccountFilter, RpcError> {
+    if let Some(size) = filter.get("dataSize").and_then(|s| s.as_u64()) {
+        return Ok(AccountFilter::DataSize(size as usize));
+    }
+
+    let memcmp = filter
+        .get("memcmp")
+        .ok_or_else(|| RpcError::new(-32602, "Unsupported filter".to_string()))?;
+
+    let offset = memcmp
+        .get("offset")
+        .and_then(|o| o.as_u64())
+        .ok_or_else(|| RpcError::new(-32602, "Missing memcmp offset".to_string()))?;
+    let encoded = memcmp
+        .get("bytes")
+        .and_then(|b| b.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing memcmp bytes".to_string()))?;
+
+    let bytes = match memcmp.get("encoding").and_then(|e| e.as_str()) {
+        Some("base64") => general_purpose::STANDARD.decode(encoded).map_err(|e| e.to_string()),
+        _ => bs58::decode(encoded).into_vec().map_err(|e| e.to_string()),
+    }
+    .map_err(|e| RpcError::new(-32602, format!("Invalid memcmp bytes: {}", e)))?;
+
+    Ok(AccountFilter::Memcmp {
+        offset: offset as usize,
+        bytes,
+    })
+}
+
+fn subscribe_logs(params: &[Value]) -> Result<Subscription, RpcError> {
+    match params.first() {
+        Some(Value::String(filter)) if filter == "all" || filter == "allWithVotes" => {
+            Ok(Subscription::Logs { mentions: None })
+        }
+        Some(filter @ Value::Object(_)) => {
+            let mentions = filter
+                .get("mentions")
+                .and_then(|m| m.as_array())
+                .filter(|m| m.len() == 1)
+                .ok_or_else(|| {
+                    RpcError::new(-32602, "mentions must contain exactly one address".to_string())
+                })?;
+
+            Ok(Subscription::Logs {
+                mentions: Some(parse_pubkey(mentions.first(), "mentions address")?),
+            })
+        }
+        _ => Err(RpcError::new(-32602, "Invalid logs filter".to_string())),
+    }
+}
+
+/// Subscribe to a signature. A transaction the fork already processed is
+/// reported straight away instead of waiting for an event that never comes.
+async fn subscribe_signature(
+    state: &AppState,
+    fork_id: &str,
+    subscriptions: &mut Subscriptions,
+    params: &[Value],
+    outgoing: &mut Vec<String>,
+) -> Result<Value, RpcError> {
+    let signature_str = params
+        .first()
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing signature parameter".to_string()))?;
+    Signature::from_str(signature_str)
+        .map_err(|e| RpcError::new(-32602, format!("Invalid signature: {}", e)))?;
+
+    let processed = {
+        let fork = state
+            .fork_manager
+            .get_fork(fork_id)
+            .ok_or_else(|| RpcError::new(-32602, "Fork not found".to_string()))?;
+        let fork = fork.read().await;
+
+        fork.history
+            .get(signature_str)
+            .map(|record| (record.err.clone(), record.slot))
+    };
+
+    let subscription = Subscription::Signature {
+        signature: signature_str.to_string(),
+    };
+
+    match processed {
+        Some((err, slot)) => {
+            // Signature subscriptions end after their one notification
+            let id = subscriptions.next_id;
+            subscriptions.next_id += 1;
+            outgoing.push(to_text(&RpcNotification::new(
+                "signatureNotification",
+                id,
+                signature_result(slot, &err),
+            )));
+            Ok(json!(id))
+        }
+        None => Ok(subscriptions.add(subscription)),
+    }
+}
+
+fn unsubscribe(subscriptions: &mut Subscriptions, params: &[Value]) -> Result<Value, RpcError> {
+    let id = params
+        .first()
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| RpcError::new(-32602, "Missing subscription ID".to_string()))?;
+
+    subscriptions
+        .active
+        .remove(&id)
+        .map(|_| json!(true))
+        .ok_or_else(|| RpcError::new(-32602, "Invalid subscription id.".to_string()))
+}
+
+/// Build the notifications an event triggers on this connection
+fn notifications_for(subscriptions: &mut Subscriptions, event: &ForkEvent) -> Vec<String> {
+    let mut outgoing = Vec::new();
+    let mut finished = Vec::new();
+
+    for (id, subscription) in &subscriptions.active {
+        let notification = match (subscription, event) {
+            (
+                Subscription::Account { pubkey, encoding },
+                ForkEvent::Account { pubkey: changed, account, slot },
+            ) if pubkey == changed => Some(RpcNotification::new(
+                "accountNotification",
+                *id,
+                json!({
+                    "context": {"slot": slot},
+                    "value": account_to_json(account, encoding)
+                }),
+            )),
+            (
+                Subscription::Program { program_id, encoding, filters },
+                ForkEvent::Account { pubkey, account, slot },
+            ) if account.owner == *program_id && filters.iter().all(|f| f.matches(account)) => {
+                Some(RpcNotification::new(
+                    "programNotification",
+                    *id,
+                    json!({
+                        "context": {"slot": slot},
+                        "value": {
+                            "pubkey": pubkey.to_string(),
+                            "account": account_to_json(account, encoding)
+                        }
+                    }),
+                ))
+            }
+            (
+                Subscription::Signature { signature },
+                ForkEvent::Transaction { signature: processed, err, slot, .. },
+            ) if signature == processed => {
+                finished.push(*id);
+                Some(RpcNotification::new(
+                    "signatureNotification",
+                    *id,
+                    signature_result(*slot, err),
+                ))
+            }
+            (
+                Subscription::Logs { mentions },
+                ForkEvent::Transaction { signature, err, logs, account_keys, slot },
+            ) if mentions.is_none_or(|address| account_keys.contains(&address)) => {
+                Some(RpcNotification::new(
+                    "logsNotification",
+                    *id,
+                    json!({
+                        "context": {"slot": slot},
+                        "value": {
+                            "signature": signature,
+                            "err": err.as_ref().map(|e| serde_json::to_value(e).unwrap_or(Value::Null)),
+                            "logs": logs
+                        }
+                    }),
+                ))
+            }
+            (Subscription::Slot, ForkEvent::Slot { slot }) => Some(RpcNotification::new(
+                "slotNotification",
+                *id,
+                json!({
+                    "parent": slot.saturating_sub(1),
+                    "root": slot,
+                    "slot": slot
+                }),
+            )),
+            _ => None,
+        };
+
+        if let Some(notification) = notification {
+            outgoing.push(to_text(&notification));
+        }
+    }
+
+    for id in finished {
+        subscriptions.active.remove(&id);
+    }
+
+    outgoing
+}
+
+/// After missing events, report the pending signature subscriptions whose
+/// transaction has landed since, as their one notification may have been lost
+async fn missed_signature_notifications(
+    state: &AppState,
+    fork_id: &str,
+    subscriptions: &mut Subscriptions,
+) -> Vec<String> {
+    let Some(fork) = state.fork_manager.get_fork(fork_id) else {
+        return Vec::new();
+    };
+    let fork = fork.read().await;
+
+    let processed: Vec<(u64, u64, Option<solana_sdk::transaction::TransactionError>)> = subscriptions
+        .active
+        .iter()
+        .filter_map(|(id, subscription)| match subscription {
+            Subscription::Signature { signature } => fork
+                .history
+                .get(signature)
+                .map(|record| (*id, record.slot, record.err.clone())),
+            _ => None,
+        })
+        .collect();
+
+    processed
+        .into_iter()
+        .map(|(id, slot, err)| {
+            subscriptions.active.remove(&id);
+            to_text(&RpcNotification::new("signatureNotification", id, signature_result(slot, &err)))
+        })
+        .collect()
+}
+
+fn signature_result(slot: u64, err: &Option<solana_sdk::transaction::TransactionError>) -> Value {
+    json!({
+        "context": {"slot": slot},
+        "value": {
+            "err": err.as_ref().map(|e| serde_json::to_value(e).unwrap_or(Value::Null))
+        }
+    })
+}
+
+fn to_text(message: &impl serde::Serialize) -> String {
+    serde_json::to_string(message).unwrap_or_default()
+}
@@ -1,18 +1,32 @@
 use axum::{
-    extract::{Query, State},
+    body::Bytes,
+    extract::{Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
+use solana_account::Account;
+use solana_message::{compiled_instruction::CompiledInstruction, VersionedMessage};
 use solana_pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction::versioned::VersionedTransaction;
 use std::str::FromStr;
 use base64::{Engine as _, engine::general_purpose};
 
 
 use crate::{
-    models::{RpcRequest, RpcResponse},
+    models::{
+        AccountUpdate, DataPatch, Fork, RpcError, RpcRequest, RpcResponse, RpcResponseBody, TokenBalance,
+        TransactionRecord,
+    },
+    services::{
+        fork_manager::{ForkManager, SharedFork},
+        load_accounts, load_transaction_accounts,
+    },
     state::AppState,
+    utils::{account_to_json, decode_account_data},
 };
 
 #[derive(Debug, Deserialize)]
@@ -21,81 +35,467 @@ pub struct RpcQuery {
     pub fork_id: Option<String>,
 }
 
+/// Which fork a JSON-RPC request is addressed to
+enum ForkSelector {
+    Fork(String),
+    User(String),
+}
+
 /// Main RPC endpoint - handles Solana JSON-RPC requests
 pub async fn handle_rpc(
     State(state): State<AppState>,
     Query(query): Query<RpcQuery>,
-    Json(request): Json<RpcRequest>,
-) -> Result<Json<RpcResponse>, StatusCode> {
-    // Validate JSON-RPC version
-    if request.jsonrpc != "2.0" {
-        return Ok(Json(RpcResponse::error(
-            request.id,
-            -32600,
-            "Invalid JSON-RPC version".to_string(),
-        )));
-    }
+    body: Bytes,
+) -> Response {
+    let selector = if let Some(fid) = query.fork_id {
+        Some(ForkSelector::Fork(fid))
+    } else {
+        query.user_id.map(ForkSelector::User)
+    };
+
+    dispatch_rpc(&state.fork_manager, selector, &body).await
+}
+
+/// Per-fork RPC endpoint - `/rpc/fork/{fork_id}`
+pub async fn handle_fork_rpc(
+    State(state): State<AppState>,
+    Path(fork_id): Path<String>,
+    body: Bytes,
+) -> Response {
+    dispatch_rpc(&state.fork_manager, Some(ForkSelector::Fork(fork_id)), &body).await
+}
 
-    // Get fork manager
-    let manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// Per-user RPC endpoint - `/rpc/user/{user_id}`, serves the user's current fork
+pub async fn handle_user_rpc(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    body: Bytes,
+) -> Response {
+    dispatch_rpc(&state.fork_manager, Some(ForkSelector::User(user_id)), &body).await
+}
+
+/// Parse a JSON-RPC body (single request or batch) and answer it against the
+/// selected fork. Notifications get no response; a body with nothing to answer
+/// is replied to with 204 No Content. An unknown fork or user is reported as
+/// a JSON-RPC error on every request.
+async fn dispatch_rpc(
+    manager: &ForkManager,
+    selector: Option<ForkSelector>,
+    body: &[u8],
+) -> Response {
+    let payload: Value = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return Json(RpcResponseBody::Single(RpcResponse::error(
+                Value::Null,
+                -32700,
+                format!("Parse error: {}", e),
+            )))
+            .into_response();
+        }
+    };
 
     // Resolve fork_id
-    let fork_id = if let Some(fid) = query.fork_id {
-        fid
-    } else if let Some(uid) = query.user_id {
-        manager
-            .get_user_fork_id(&uid)
-            .cloned()
-            .ok_or(StatusCode::NOT_FOUND)?
-    } else {
-        return Ok(Json(RpcResponse::error(
-            request.id,
+    let fork_id = match selector {
+        Some(ForkSelector::Fork(fork_id)) => Ok(fork_id),
+        Some(ForkSelector::User(user_id)) => manager
+            .get_user_fork_id(&user_id)
+            .ok_or_else(|| RpcError::new(-32602, format!("No fork found for user {}", user_id))),
+        None => Err(RpcError::new(
             -32602,
             "Missing fork_id or user_id parameter".to_string(),
-        )));
+        )),
+    };
+
+    // Look the fork up once; each request then locks only this fork
+    let fork = fork_id.and_then(|fork_id| {
+        let fork = manager
+            .get_fork(&fork_id)
+            .ok_or_else(|| RpcError::new(-32602, format!("Fork {} not found", fork_id)))?;
+        Ok((fork_id, fork))
+    });
+
+    let response = match payload {
+        Value::Array(requests) if requests.is_empty() => Some(RpcResponseBody::Single(
+            RpcResponse::error(Value::Null, -32600, "Invalid request: empty batch".to_string()),
+        )),
+        Value::Array(requests) => {
+            let mut responses = Vec::new();
+            for request in requests {
+                responses.extend(handle_request(manager, &fork, request).await);
+            }
+            (!responses.is_empty()).then_some(RpcResponseBody::Batch(responses))
+        }
+        request => handle_request(manager, &fork, request).await.map(RpcResponseBody::Single),
     };
 
-    // Get fork
-    let fork = manager.get_fork(&fork_id).ok_or(StatusCode::NOT_FOUND)?;
+    match response {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    }
+}
 
-    // Handle different RPC methods
-    let result = match request.method.as_str() {
-        "getBalance" => handle_get_balance(fork, &request.params),
-        "getAccountInfo" => handle_get_account_info(fork, &request.params),
+/// Validate and answer a single JSON-RPC request. Notifications, valid
+/// requests without an `id` member, are executed but not answered.
+async fn handle_request(
+    manager: &ForkManager,
+    fork: &Result<(String, SharedFork), RpcError>,
+    payload: Value,
+) -> Option<RpcResponse> {
+    let is_notification = payload.is_object() && payload.get("id").is_none();
+
+    // Echo the id of malformed requests when it is usable
+    let fallback_id = match payload.get("id") {
+        Some(id @ (Value::String(_) | Value::Number(_))) => id.clone(),
+        _ => Value::Null,
+    };
+
+    let request: RpcRequest = match serde_json::from_value(payload) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(RpcResponse::error(fallback_id, -32600, format!("Invalid request: {}", e)));
+        }
+    };
+
+    // Validate JSON-RPC version and id type
+    if request.jsonrpc != "2.0" {
+        return Some(RpcResponse::error(request.id, -32600, "Invalid JSON-RPC version".to_string()));
+    }
+    if !matches!(request.id, Value::String(_) | Value::Number(_) | Value::Null) {
+        return Some(RpcResponse::error(
+            Value::Null,
+            -32600,
+            "Invalid request: id must be a string, number or null".to_string(),
+        ));
+    }
+
+    let (fork_id, fork) = match fork {
+        Ok((fork_id, fork)) => (fork_id, fork),
+        Err(_) if is_notification => return None,
+        Err(error) => return Some(RpcResponse::from_error(request.id, error.clone())),
+    };
+
+    let params = request
+        .params
+        .map(|params| params.into_positional(param_names(&request.method)));
+
+    let response = match call_method(manager, fork_id, fork, &request.method, &params).await {
+        Ok(value) => RpcResponse::success(request.id, value),
+        Err(error) => RpcResponse::from_error(request.id, error),
+    };
+
+    (!is_notification).then_some(response)
+}
+
+/// Names of the positional parameters of methods that accept named params
+fn param_names(method: &str) -> &'static [&'static str] {
+    match method {
+        "getBalance" | "getAccountInfo" => &["pubkey", "config"],
+        "sendTransaction" | "simulateTransaction" => &["transaction", "config"],
+        "getTransaction" => &["signature", "config"],
+        "getSignatureStatuses" => &["signatures", "config"],
+        "getSignaturesForAddress" => &["address", "config"],
+        "fork_revert" => &["snapshotId"],
+        "fork_setAccount" => &["pubkey", "account"],
+        "fork_warpToSlot" => &["slot"],
+        "fork_advanceSlots" => &["slots"],
+        "fork_warpToEpoch" => &["epoch"],
+        "fork_setUnixTimestamp" => &["unixTimestamp"],
+        _ => &[],
+    }
+}
+
+/// Call the handler for a JSON-RPC method. Accounts the method reads are
+/// lazy loaded first, then read-only methods share the fork's read lock.
+async fn call_method(
+    manager: &ForkManager,
+    fork_id: &str,
+    fork: &SharedFork,
+    method: &str,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    match method {
+        // Fork management methods need the manager rather than a single fork
+        "fork_snapshot" => return handle_fork_snapshot(manager, fork_id).await,
+        "fork_revert" => return handle_fork_revert(manager, fork_id, params).await,
+        _ => {}
+    }
+
+    load_method_accounts(fork, method, params).await?;
+
+    if let Some(result) = call_read_method(&*fork.read().await, method, params) {
+        return result;
+    }
+
+    let mut fork = fork.write().await;
+    let fork = &mut *fork;
+
+    match method {
+        "sendTransaction" => handle_send_transaction(fork, params),
+        "simulateTransaction" => handle_simulate_transaction(fork, params),
+        "fork_setAccount" => handle_fork_set_account(fork, params),
+        "fork_warpToSlot" => handle_fork_warp(fork, params, Fork::warp_to_slot),
+        "fork_advanceSlots" => handle_fork_warp(fork, params, Fork::advance_slots),
+        "fork_warpToEpoch" => handle_fork_warp(fork, params, Fork::warp_to_epoch),
+        "fork_setUnixTimestamp" => handle_fork_set_unix_timestamp(fork, params),
+        _ => Err(RpcError::new(-32601, format!("Method not supported: {}", method))),
+    }
+}
+
+/// Call the handler for a method that only reads the fork, or return `None`
+/// if the method needs write access
+fn call_read_method(
+    fork: &Fork,
+    method: &str,
+    params: &Option<Vec<Value>>,
+) -> Option<Result<Value, RpcError>> {
+    let result = match method {
+        "getBalance" => handle_get_balance(fork, params),
+        "getAccountInfo" => handle_get_account_info(fork, params),
         "getSlot" => handle_get_slot(fork),
         "getLatestBlockhash" => handle_get_latest_blockhash(fork),
         "getBlockHeight" => handle_get_block_height(fork),
         "getHealth" => handle_get_health(),
         "getVersion" => handle_get_version(),
-        _ => Err(format!("Method not supported: {}", request.method)),
+        "getTransaction" => handle_get_transaction(fork, params),
+        "getSignatureStatuses" => handle_get_signature_statuses(fork, params),
+        "getSignaturesForAddress" => handle_get_signatures_for_address(fork, params),
+        _ => return None,
     };
+    Some(result)
+}
+
+/// Lazy load the accounts a method reads, without holding the fork lock
+/// while upstream is queried. Malformed params are left for the method's
+/// handler to report.
+async fn load_method_accounts(
+    fork: &SharedFork,
+    method: &str,
+    params: &Option<Vec<Value>>,
+) -> Result<(), RpcError> {
+    let params = params.as_deref().unwrap_or_default();
+    let load_error = |e| RpcError::new(-32603, e);
+
+    match method {
+        "getBalance" | "getAccountInfo" | "fork_setAccount" => {
+            if let Some(pubkey) = params.first().and_then(|v| v.as_str()).and_then(|s| Pubkey::from_str(s).ok()) {
+                load_accounts(fork, &[pubkey]).await.map_err(load_error)?;
+            }
+        }
+        "sendTransaction" | "simulateTransaction" => {
+            let config = params.get(1);
+            let encoding = config
+                .and_then(|c| c.get("encoding"))
+                .and_then(|e| e.as_str())
+                .unwrap_or("base58");
+            let transaction = params
+                .first()
+                .and_then(|v| v.as_str())
+                .and_then(|encoded| decode_transaction(encoded, encoding).ok());
+            if let Some(transaction) = transaction {
+                load_transaction_accounts(fork, &transaction).await.map_err(load_error)?;
+            }
 
-    match result {
-        Ok(value) => Ok(Json(RpcResponse::success(request.id, value))),
-        Err(msg) => Ok(Json(RpcResponse::error(request.id, -32601, msg))),
+            // Accounts simulateTransaction reports the state of
+            let addresses: Vec<Pubkey> = config
+                .and_then(|c| c.get("accounts"))
+                .and_then(|a| a.get("addresses"))
+                .and_then(|a| a.as_array())
+                .map(|addresses| {
+                    addresses
+                        .iter()
+                        .filter_map(|v| v.as_str().and_then(|s| Pubkey::from_str(s).ok()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            load_accounts(fork, &addresses).await.map_err(load_error)?;
+        }
+        _ => {}
     }
+
+    Ok(())
+}
+
+/// Handle fork_snapshot RPC method
+async fn handle_fork_snapshot(manager: &ForkManager, fork_id: &str) -> Result<Value, RpcError> {
+    let snapshot_id = manager
+        .create_snapshot(fork_id)
+        .await
+        .map_err(|e| RpcError::new(-32603, e))?;
+    Ok(json!(snapshot_id))
+}
+
+/// Handle fork_revert RPC method
+async fn handle_fork_revert(
+    manager: &ForkManager,
+    fork_id: &str,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let snapshot_id = params
+        .first()
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing snapshot ID parameter".to_string()))?;
+
+    manager
+        .revert_to_snapshot(fork_id, snapshot_id)
+        .await
+        .map_err(|e| RpcError::new(-32602, e))?;
+    Ok(json!(true))
+}
+
+/// Handle fork_setAccount RPC method
+fn handle_fork_set_account(
+    fork: &mut Fork,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let pubkey_str = params
+        .first()
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing pubkey parameter".to_string()))?;
+    let pubkey = Pubkey::from_str(pubkey_str)
+        .map_err(|e| RpcError::new(-32602, format!("Invalid pubkey: {}", e)))?;
+
+    let fields = params
+        .get(1)
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| RpcError::new(-32602, "Missing account object".to_string()))?;
+    let invalid = |field: &str| RpcError::new(-32602, format!("Invalid {}", field));
+
+    let owner = match fields.get("owner") {
+        Some(owner) => Some(
+            owner
+                .as_str()
+                .and_then(|owner| Pubkey::from_str(owner).ok())
+                .ok_or_else(|| invalid("owner"))?,
+        ),
+        None => None,
+    };
+    let data = fields.get("data").map(decode_data_param).transpose()?;
+    let patches = match fields.get("patches") {
+        Some(patches) => patches
+            .as_array()
+            .ok_or_else(|| invalid("patches"))?
+            .iter()
+            .map(|patch| {
+                let offset = patch
+                    .get("offset")
+                    .and_then(|v| v.as_u64())
+                    .ok_or_else(|| invalid("patch offset"))?;
+                let data = patch.get("data").ok_or_else(|| invalid("patch data"))?;
+                Ok(DataPatch {
+                    offset: offset as usize,
+                    bytes: decode_data_param(data)?,
+                })
+            })
+            .collect::<Result<Vec<_>, RpcError>>()?,
+        None => Vec::new(),
+    };
+
+    let u64_field = |field: &str| {
+        fields
+            .get(field)
+            .map(|v| v.as_u64().ok_or_else(|| invalid(field)))
+            .transpose()
+    };
+    let update = AccountUpdate {
+        lamports: u64_field("lamports")?,
+        owner,
+        executable: fields
+            .get("executable")
+            .map(|v| v.as_bool().ok_or_else(|| invalid("executable")))
+            .transpose()?,
+        rent_epoch: u64_field("rentEpoch")?,
+        data,
+        patches,
+    };
+
+    let account = fork
+        .set_account(pubkey, update)
+        .map_err(|e| RpcError::new(-32602, e))?;
+
+    Ok(account_to_json(&account, "base64"))
+}
+
+/// Decode account data given as `[data, encoding]`, like getAccountInfo
+/// returns it, or as a bare base64 string
+fn decode_data_param(value: &Value) -> Result<Vec<u8>, RpcError> {
+    let (data, encoding) = match value {
+        Value::String(data) => (data.as_str(), "base64"),
+        Value::Array(parts) => (
+            parts.first().and_then(|v| v.as_str()).unwrap_or_default(),
+            parts.get(1).and_then(|v| v.as_str()).unwrap_or("base64"),
+        ),
+        _ => return Err(RpcError::new(-32602, "Invalid data".to_string())),
+    };
+
+    decode_account_data(data, encoding).map_err(|e| RpcError::new(-32602, e))
+}
+
+/// Handle fork_warpToSlot, fork_advanceSlots and fork_warpToEpoch, which all
+/// take a single integer and move the fork forward
+fn handle_fork_warp(
+    fork: &mut Fork,
+    params: &Option<Vec<Value>>,
+    warp: fn(&mut Fork, u64) -> Result<(), String>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let target = params
+        .first()
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| RpcError::new(-32602, "Expected a non-negative integer parameter".to_string()))?;
+
+    warp(fork, target).map_err(|e| RpcError::new(-32602, e))?;
+    Ok(clock_to_json(fork))
+}
+
+/// Handle fork_setUnixTimestamp RPC method
+fn handle_fork_set_unix_timestamp(
+    fork: &mut Fork,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let unix_timestamp = params
+        .first()
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| RpcError::new(-32602, "Expected an integer unix timestamp".to_string()))?;
+
+    fork.set_unix_timestamp(unix_timestamp);
+    Ok(clock_to_json(fork))
+}
+
+fn clock_to_json(fork: &Fork) -> Value {
+    let clock = fork.clock();
+    json!({
+        "slot": fork.slot,
+        "epoch": clock.epoch,
+        "unixTimestamp": clock.unix_timestamp,
+        "blockhash": fork.latest_blockhash().to_string()
+    })
 }
 
 /// Handle getBalance RPC method
 fn handle_get_balance(
     fork: &crate::models::Fork,
     params: &Option<Vec<Value>>,
-) -> Result<Value, String> {
-    let params = params.as_ref().ok_or("Missing parameters")?;
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
     
     if params.is_empty() {
-        return Err("Missing address parameter".to_string());
+        return Err(RpcError::new(-32602, "Missing address parameter".to_string()));
     }
 
     let address_str = params[0]
         .as_str()
-        .ok_or("Invalid address format")?;
+        .ok_or_else(|| RpcError::new(-32602, "Invalid address format".to_string()))?;
 
     let pubkey = Pubkey::from_str(address_str)
-        .map_err(|e| format!("Invalid pubkey: {}", e))?;
+        .map_err(|e| RpcError::new(-32602, format!("Invalid pubkey: {}", e)))?;
 
     let lamports = fork.get_balance(&pubkey);
 
@@ -111,14 +511,14 @@ fn handle_get_balance(
 fn handle_get_account_info(
     fork: &crate::models::Fork,
     params: &Option<Vec<Value>>,
-) -> Result<Value, String> {
-    let params = params.as_ref().ok_or("Missing parameters")?;
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
     
     if params.is_empty() {
-        return Err("Missing address parameter".to_string());
+        return Err(RpcError::new(-32602, "Missing address parameter".to_string()));
     }
 
-    let address_str = params[0].as_str().ok_or("Invalid address format")?;
+    let address_str = params[0].as_str().ok_or_else(|| RpcError::new(-32602, "Invalid address format".to_string()))?;
     
     // Check for encoding option (params[1])
     let encoding = params.get(1)
@@ -127,63 +527,622 @@ fn handle_get_account_info(
         .unwrap_or("base64");
 
     let pubkey = Pubkey::from_str(address_str)
-        .map_err(|e| format!("Invalid pubkey: {}", e))?;
-
-    match fork.svm.get_account(&pubkey) {
-        Some(account) => {
-            let encoded_data = match encoding {
-                "base64" => general_purpose::STANDARD.encode(&account.data),
-                "base58" => bs58::encode(&account.data).into_string(),
-                _ => general_purpose::STANDARD.encode(&account.data),
-            };
-            
-            Ok(json!({
-                "context": {"slot": fork.slot},
-                "value": {
-                    "lamports": account.lamports,
-                    "owner": account.owner.to_string(),
-                    "executable": account.executable,
-                    "rentEpoch": account.rent_epoch,
-                    "data": [encoded_data, encoding]
-                }
-            }))
-        },
+        .map_err(|e| RpcError::new(-32602, format!("Invalid pubkey: {}", e)))?;
+
+    match fork.get_account(&pubkey) {
+        Some(account) => Ok(json!({
+            "context": {"slot": fork.slot},
+            "value": account_to_json(&account, encoding)
+        })),
         None => Ok(json!({"context": {"slot": fork.slot}, "value": null})),
     }
 }
 
+/// Decode a wire-format transaction (legacy or versioned) sent over JSON-RPC
+fn decode_transaction(encoded: &str, encoding: &str) -> Result<VersionedTransaction, RpcError> {
+    let bytes = match encoding {
+        "base58" => bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| RpcError::new(-32602, format!("Invalid base58 transaction: {}", e)))?,
+        "base64" => general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| RpcError::new(-32602, format!("Invalid base64 transaction: {}", e)))?,
+        _ => {
+            return Err(RpcError::new(
+                -32602,
+                format!("Unsupported transaction encoding: {}", encoding),
+            ))
+        }
+    };
+
+    bincode::deserialize(&bytes)
+        .map_err(|e| RpcError::new(-32602, format!("Failed to deserialize transaction: {}", e)))
+}
+
+/// Handle sendTransaction RPC method
+fn handle_send_transaction(
+    fork: &mut crate::models::Fork,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let encoded = params
+        .first()
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing transaction parameter".to_string()))?;
+
+    let config = params.get(1);
+    let encoding = config
+        .and_then(|c| c.get("encoding"))
+        .and_then(|e| e.as_str())
+        .unwrap_or("base58");
+    let skip_preflight = config
+        .and_then(|c| c.get("skipPreflight"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let include_diff = config
+        .and_then(|c| c.get("includeAccountDiff"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let transaction = decode_transaction(encoded, encoding)?;
+
+    // Preflight: reject transactions that would fail without executing them
+    if !skip_preflight {
+        let sigverify = fork.svm.get_sigverify();
+        if let Err(failed) = fork
+            .simulate_transaction(transaction.clone(), sigverify)
+            .map_err(|e| RpcError::new(-32603, e))?
+        {
+            return Err(RpcError::new(
+                -32002,
+                format!("Transaction simulation failed: {}", failed.err),
+            )
+            .with_data(json!({
+                "err": serde_json::to_value(&failed.err).unwrap_or(Value::Null),
+                "logs": failed.meta.logs,
+                "accounts": null,
+                "unitsConsumed": failed.meta.compute_units_consumed,
+                "returnData": return_data_to_json(&failed.meta.return_data.program_id, &failed.meta.return_data.data),
+            })));
+        }
+    }
+
+    // Like a real cluster, a transaction that fails on-chain still returns its signature
+    let result = fork
+        .send_transaction(transaction, include_diff)
+        .map_err(|e| RpcError::new(-32603, e))?;
+
+    // The diff is an extension; without it the result stays a bare signature
+    match result.account_diffs {
+        Some(diffs) => Ok(json!({
+            "signature": result.signature,
+            "accountDiffs": serde_json::to_value(diffs).unwrap_or(Value::Null),
+        })),
+        None => Ok(json!(result.signature)),
+    }
+}
+
+/// Handle simulateTransaction RPC method
+fn handle_simulate_transaction(
+    fork: &mut crate::models::Fork,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let encoded = params
+        .first()
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing transaction parameter".to_string()))?;
+
+    let config = params.get(1);
+    let encoding = config
+        .and_then(|c| c.get("encoding"))
+        .and_then(|e| e.as_str())
+        .unwrap_or("base58");
+    let sig_verify = config
+        .and_then(|c| c.get("sigVerify"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let replace_recent_blockhash = config
+        .and_then(|c| c.get("replaceRecentBlockhash"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    if sig_verify && replace_recent_blockhash {
+        return Err(RpcError::new(
+            -32602,
+            "sigVerify may not be used with replaceRecentBlockhash".to_string(),
+        ));
+    }
+
+    let mut transaction = decode_transaction(encoded, encoding)?;
+
+    let replacement_blockhash = if replace_recent_blockhash {
+        let blockhash = fork.latest_blockhash();
+        transaction.message.set_recent_blockhash(blockhash);
+        Some(blockhash)
+    } else {
+        None
+    };
+
+    let (err, meta, post_accounts) = match fork
+        .simulate_transaction(transaction, sig_verify)
+        .map_err(|e| RpcError::new(-32603, e))?
+    {
+        Ok(info) => (None, info.meta, info.post_accounts),
+        Err(failed) => (Some(failed.err), failed.meta, Vec::new()),
+    };
+
+    // Post-simulation state of the requested accounts
+    let accounts = match config.and_then(|c| c.get("accounts")) {
+        Some(accounts_config) => {
+            let account_encoding = accounts_config
+                .get("encoding")
+                .and_then(|e| e.as_str())
+                .unwrap_or("base64");
+
+            let addresses = accounts_config
+                .get("addresses")
+                .and_then(|a| a.as_array())
+                .ok_or_else(|| RpcError::new(-32602, "Missing accounts.addresses parameter".to_string()))?;
+
+            let mut encoded_accounts = Vec::with_capacity(addresses.len());
+            for address in addresses {
+                let address_str = address.as_str().ok_or_else(|| RpcError::new(-32602, "Invalid address format".to_string()))?;
+                let pubkey = Pubkey::from_str(address_str)
+                    .map_err(|e| RpcError::new(-32602, format!("Invalid pubkey: {}", e)))?;
+
+                let account = if err.is_some() {
+                    None
+                } else if let Some((_, account)) = post_accounts.iter().find(|(key, _)| *key == pubkey) {
+                    Some(Account::from(account.clone()))
+                } else {
+                    fork.get_account(&pubkey)
+                };
+
+                encoded_accounts.push(
+                    account
+                        .map(|account| account_to_json(&account, account_encoding))
+                        .unwrap_or(Value::Null),
+                );
+            }
+
+            Value::Array(encoded_accounts)
+        }
+        None => Value::Null,
+    };
+
+    let mut value = json!({
+        "err": err.map(|e| serde_json::to_value(&e).unwrap_or(Value::Null)),
+        "logs": meta.logs,
+        "accounts": accounts,
+        "unitsConsumed": meta.compute_units_consumed,
+        "returnData": return_data_to_json(&meta.return_data.program_id, &meta.return_data.data),
+    });
+
+    if let Some(blockhash) = replacement_blockhash {
+        value["replacementBlockhash"] = json!({
+            "blockhash": blockhash.to_string(),
+            "lastValidBlockHeight": fork.slot + 150
+        });
+    }
+
+    Ok(json!({
+        "context": {"slot": fork.slot},
+        "value": value
+    }))
+}
+
+/// Encode program return data, or null if the transaction set none
+fn return_data_to_json(program_id: &Pubkey, data: &[u8]) -> Value {
+    if data.is_empty() {
+        return Value::Null;
+    }
+
+    json!({
+        "programId": program_id.to_string(),
+        "data": [general_purpose::STANDARD.encode(data), "base64"]
+    })
+}
+
 /// Handle getSlot RPC method
-fn handle_get_slot(fork: &crate::models::Fork) -> Result<Value, String> {
+fn handle_get_slot(fork: &crate::models::Fork) -> Result<Value, RpcError> {
     Ok(json!(fork.slot))
 }
 
 /// Handle getLatestBlockhash RPC method
-fn handle_get_latest_blockhash(fork: &crate::models::Fork) -> Result<Value, String> {
+fn handle_get_latest_blockhash(fork: &crate::models::Fork) -> Result<Value, RpcError> {
+    let blockhash = fork.latest_blockhash().to_string();
+
     Ok(json!({
         "context": {
             "slot": fork.slot
         },
         "value": {
-            "blockhash": fork.mainnet_blockhash,
+            "blockhash": blockhash,
             "lastValidBlockHeight": fork.slot + 150
         }
     }))
 }
 
 /// Handle getBlockHeight RPC method
-fn handle_get_block_height(fork: &crate::models::Fork) -> Result<Value, String> {
+fn handle_get_block_height(fork: &crate::models::Fork) -> Result<Value, RpcError> {
     Ok(json!(fork.slot))
 }
 
 /// Handle getHealth RPC method
-fn handle_get_health() -> Result<Value, String> {
+fn handle_get_health() -> Result<Value, RpcError> {
     Ok(json!("ok"))
 }
 
 /// Handle getVersion RPC method
-fn handle_get_version() -> Result<Value, String> {
+fn handle_get_version() -> Result<Value, RpcError> {
     Ok(json!({
         "solana-core": "3.0.0",
         "feature-set": 0
     }))
-}
\ No newline at end of file
+}
+
+/// Largest number of signatures `getSignatureStatuses` accepts
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// Default and largest page size of `getSignaturesForAddress`
+const MAX_SIGNATURES_FOR_ADDRESS: usize = 1000;
+
+/// Handle getTransaction RPC method
+fn handle_get_transaction(
+    fork: &crate::models::Fork,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let signature = params
+        .first()
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing signature parameter".to_string()))?;
+
+    // The config may also be given as a bare encoding string (deprecated form)
+    let config = params.get(1);
+    let encoding = config
+        .and_then(|c| c.as_str().or_else(|| c.get("encoding").and_then(|e| e.as_str())))
+        .unwrap_or("json");
+    let max_supported_version = config
+        .and_then(|c| c.get("maxSupportedTransactionVersion"))
+        .and_then(|v| v.as_u64());
+
+    let Some(record) = fork.history.get(signature) else {
+        return Ok(Value::Null);
+    };
+
+    let version = match &record.transaction.message {
+        VersionedMessage::Legacy(_) => json!("legacy"),
+        VersionedMessage::V0(_) => {
+            if max_supported_version.is_none() {
+                return Err(RpcError::new(
+                    -32015,
+                    "Transaction version (0) is not supported by the requesting client. \
+                     Please try the request again with the following configuration parameter: \
+                     \"maxSupportedTransactionVersion\": 0"
+                        .to_string(),
+                ));
+            }
+            json!(0)
+        }
+    };
+
+    let transaction = match encoding {
+        "base64" | "base58" => {
+            let bytes = bincode::serialize(&record.transaction)
+                .map_err(|e| RpcError::new(-32603, format!("Failed to serialize transaction: {}", e)))?;
+            let encoded = if encoding == "base64" {
+                general_purpose::STANDARD.encode(bytes)
+            } else {
+                bs58::encode(bytes).into_string()
+            };
+            json!([encoded, encoding])
+        }
+        _ => transaction_to_json(&record.transaction),
+    };
+
+    Ok(json!({
+        "slot": record.slot,
+        "blockTime": record.block_time,
+        "version": version,
+        "transaction": transaction,
+        "meta": transaction_meta_to_json(record)
+    }))
+}
+
+/// Handle getSignatureStatuses RPC method
+fn handle_get_signature_statuses(
+    fork: &crate::models::Fork,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let signatures = params
+        .first()
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| RpcError::new(-32602, "Missing signatures parameter".to_string()))?;
+
+    if signatures.len() > MAX_SIGNATURE_STATUSES {
+        return Err(RpcError::new(
+            -32602,
+            format!("Too many inputs provided; max {}", MAX_SIGNATURE_STATUSES),
+        ));
+    }
+
+    let statuses: Vec<Value> = signatures
+        .iter()
+        .map(|signature| {
+            signature
+                .as_str()
+                .and_then(|signature| fork.history.get(signature))
+                .map(|record| {
+                    json!({
+                        "slot": record.slot,
+                        "confirmations": null,
+                        "err": transaction_error_to_json(&record.err),
+                        "status": transaction_status_to_json(&record.err),
+                        "confirmationStatus": "finalized"
+                    })
+                })
+                .unwrap_or(Value::Null)
+        })
+        .collect();
+
+    Ok(json!({
+        "context": {"slot": fork.slot},
+        "value": statuses
+    }))
+}
+
+/// Handle getSignaturesForAddress RPC method
+fn handle_get_signatures_for_address(
+    fork: &crate::models::Fork,
+    params: &Option<Vec<Value>>,
+) -> Result<Value, RpcError> {
+    let params = params.as_ref().ok_or_else(|| RpcError::new(-32602, "Missing parameters".to_string()))?;
+
+    let address_str = params
+        .first()
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::new(-32602, "Missing address parameter".to_string()))?;
+    let address = Pubkey::from_str(address_str)
+        .map_err(|e| RpcError::new(-32602, format!("Invalid pubkey: {}", e)))?;
+
+    let config = params.get(1);
+    let limit = config
+        .and_then(|c| c.get("limit"))
+        .and_then(|l| l.as_u64())
+        .map_or(MAX_SIGNATURES_FOR_ADDRESS, |l| l as usize);
+    if limit == 0 || limit > MAX_SIGNATURES_FOR_ADDRESS {
+        return Err(RpcError::new(
+            -32602,
+            format!("Invalid limit; max {}", MAX_SIGNATURES_FOR_ADDRESS),
+        ));
+    }
+    let before = config.and_then(|c| c.get("before")).and_then(|b| b.as_str());
+    let until = config.and_then(|c| c.get("until")).and_then(|u| u.as_str());
+
+    let signatures: Vec<Value> = fork
+        .history
+        .signatures_for_address(&address, before, until, limit)
+        .into_iter()
+        .map(|record| {
+            json!({
+                "signature": record.signature,
+                "slot": record.slot,
+                "err": transaction_error_to_json(&record.err),
+                "memo": null,
+                "blockTime": record.block_time,
+                "confirmationStatus": "finalized"
+            })
+        })
+        .collect();
+
+    Ok(json!(signatures))
+}
+
+fn transaction_error_to_json(err: &Option<TransactionError>) -> Value {
+    err.as_ref()
+        .map(|e| serde_json::to_value(e).unwrap_or(Value::Null))
+        .unwrap_or(Value::Null)
+}
+
+/// Legacy `status` field: `{"Ok": null}` or `{"Err": ...}`
+fn transaction_status_to_json(err: &Option<TransactionError>) -> Value {
+    match err {
+        None => json!({"Ok": null}),
+        Some(_) => json!({"Err": transaction_error_to_json(err)}),
+    }
+}
+
+/// Encode a transaction with the `json` encoding
+fn transaction_to_json(transaction: &VersionedTransaction) -> Value {
+    let message = &transaction.message;
+    let header = message.header();
+
+    let instructions: Vec<Value> = message
+        .instructions()
+        .iter()
+        .map(|instruction| compiled_instruction_to_json(instruction, None))
+        .collect();
+
+    let mut message_json = json!({
+        "accountKeys": message
+            .static_account_keys()
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>(),
+        "header": {
+            "numRequiredSignatures": header.num_required_signatures,
+            "numReadonlySignedAccounts": header.num_readonly_signed_accounts,
+            "numReadonlyUnsignedAccounts": header.num_readonly_unsigned_accounts
+        },
+        "recentBlockhash": message.recent_blockhash().to_string(),
+        "instructions": instructions
+    });
+
+    if let Some(lookups) = message.address_table_lookups() {
+        message_json["addressTableLookups"] = lookups
+            .iter()
+            .map(|lookup| {
+                json!({
+                    "accountKey": lookup.account_key.to_string(),
+                    "writableIndexes": lookup.writable_indexes,
+                    "readonlyIndexes": lookup.readonly_indexes
+                })
+            })
+            .collect();
+    }
+
+    json!({
+        "signatures": transaction
+            .signatures
+            .iter()
+            .map(|signature| signature.to_string())
+            .collect::<Vec<_>>(),
+        "message": message_json
+    })
+}
+
+fn compiled_instruction_to_json(instruction: &CompiledInstruction, stack_height: Option<u8>) -> Value {
+    json!({
+        "programIdIndex": instruction.program_id_index,
+        "accounts": instruction.accounts,
+        "data": bs58::encode(&instruction.data).into_string(),
+        "stackHeight": stack_height
+    })
+}
+
+/// Build the `meta` object of a getTransaction response
+fn transaction_meta_to_json(record: &TransactionRecord) -> Value {
+    let inner_instructions: Vec<Value> = record
+        .meta
+        .inner_instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, instructions)| !instructions.is_empty())
+        .map(|(index, instructions)| {
+            json!({
+                "index": index,
+                "instructions": instructions
+                    .iter()
+                    .map(|inner| compiled_instruction_to_json(&inner.instruction, Some(inner.stack_height)))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    let mut meta = json!({
+        "err": transaction_error_to_json(&record.err),
+        "status": transaction_status_to_json(&record.err),
+        "fee": record.fee,
+        "preBalances": record.pre_balances,
+        "postBalances": record.post_balances,
+        "innerInstructions": inner_instructions,
+        "logMessages": record.meta.logs,
+        "preTokenBalances": record.pre_token_balances.iter().map(token_balance_to_json).collect::<Vec<_>>(),
+        "postTokenBalances": record.post_token_balances.iter().map(token_balance_to_json).collect::<Vec<_>>(),
+        "rewards": [],
+        "loadedAddresses": {
+            "writable": record.loaded_writable.iter().map(|key| key.to_string()).collect::<Vec<_>>(),
+            "readonly": record.loaded_readonly.iter().map(|key| key.to_string()).collect::<Vec<_>>()
+        },
+        "computeUnitsConsumed": record.meta.compute_units_consumed
+    });
+
+    let return_data = return_data_to_json(
+        &record.meta.return_data.program_id,
+        &record.meta.return_data.data,
+    );
+    if !return_data.is_null() {
+        meta["returnData"] = return_data;
+    }
+
+    meta
+}
+
+fn token_balance_to_json(balance: &TokenBalance) -> Value {
+    json!({
+        "accountIndex": balance.account_index,
+        "mint": balance.mint.to_string(),
+        "owner": balance.owner.to_string(),
+        "programId": balance.program_id.to_string(),
+        "uiTokenAmount": {
+            "amount": balance.amount.to_string(),
+            "decimals": balance.decimals,
+            "uiAmount": spl_token::amount_to_ui_amount(balance.amount, balance.decimals),
+            "uiAmountString": spl_token::amount_to_ui_amount_string_trimmed(balance.amount, balance.decimals)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn dispatch(manager: &ForkManager, selector: ForkSelector, body: Value) -> (StatusCode, Option<Value>) {
+        let response = dispatch_rpc(manager, Some(selector), body.to_string().as_bytes()).await;
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).ok())
+    }
+
+    fn new_fork() -> (ForkManager, ForkSelector) {
+        let manager = ForkManager::new();
+        let fork_id = manager.create_fork("alice".to_string()).unwrap();
+        (manager, ForkSelector::Fork(fork_id))
+    }
+
+    #[tokio::test]
+    async fn notifications_get_no_response() {
+        let (manager, selector) = new_fork();
+        let (status, body) = dispatch(&manager, selector, json!([
+            {"jsonrpc": "2.0", "method": "getSlot"},
+            {"jsonrpc": "2.0", "id": 7, "method": "getSlot"},
+        ])).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let responses = body.unwrap();
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["id"], 7);
+    }
+
+    #[tokio::test]
+    async fn notification_only_batch_is_no_content() {
+        let (manager, selector) = new_fork();
+        let (status, body) = dispatch(&manager, selector, json!([
+            {"jsonrpc": "2.0", "method": "getSlot"},
+            {"jsonrpc": "2.0", "method": "getHealth"},
+        ])).await;
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(body.is_none());
+    }
+
+    #[tokio::test]
+    async fn empty_batch_is_invalid_request() {
+        let (manager, selector) = new_fork();
+        let (status, body) = dispatch(&manager, selector, json!([])).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.unwrap()["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn unknown_fork_or_user_is_rpc_error() {
+        let manager = ForkManager::new();
+        for selector in [ForkSelector::Fork("fork-missing".to_string()), ForkSelector::User("bob".to_string())] {
+            let (status, body) = dispatch(&manager, selector, json!({"jsonrpc": "2.0", "id": 1, "method": "getSlot"})).await;
+
+            assert_eq!(status, StatusCode::OK);
+            let body = body.unwrap();
+            assert_eq!(body["id"], 1);
+            assert_eq!(body["error"]["code"], -32602);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    models::{RevertRequest, RevertResponse, SnapshotRequest, SnapshotResponse},
+    state::AppState,
+    utils::resolve_fork_or_user_id,
+};
+
+/// Snapshot the full state of a fork
+pub async fn create_snapshot(
+    State(state): State<AppState>,
+    Json(payload): Json<SnapshotRequest>,
+) -> Result<Json<SnapshotResponse>, (StatusCode, String)> {
+    let manager = &state.fork_manager;
+
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+
+    if manager.get_fork(&fork_id).is_none() {
+        return Err((StatusCode::NOT_FOUND, "Fork not found".to_string()));
+    }
+
+    let snapshot_id = manager
+        .create_snapshot(&fork_id)
+        .await
+        .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, e))?;
+
+    let slot = match manager.get_fork(&fork_id) {
+        Some(fork) => fork.read().await.slot,
+        None => 0,
+    };
+
+    Ok(Json(SnapshotResponse {
+        success: true,
+        fork_id,
+        snapshot_id,
+        slot,
+    }))
+}
+
+/// Revert a fork to a previously taken snapshot
+pub async fn revert_snapshot(
+    State(state): State<AppState>,
+    Json(payload): Json<RevertRequest>,
+) -> Result<Json<RevertResponse>, (StatusCode, String)> {
+    let manager = &state.fork_manager;
+
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+
+    let slot = manager
+        .revert_to_snapshot(&fork_id, &payload.snapshot_id)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e))?;
+
+    Ok(Json(RevertResponse {
+        success: true,
+        fork_id,
+        snapshot_id: payload.snapshot_id,
+        slot,
+    }))
+}
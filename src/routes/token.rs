@@ -1,22 +1,41 @@
 #![allow(deprecated)]
 
 use axum::{extract::State, http::StatusCode, Json};
+use solana_account::Account;
+use solana_instruction::{AccountMeta, Instruction};
 use solana_pubkey::Pubkey;
 use solana_keypair::Keypair;
 use solana_signer::Signer;
 use solana_transaction::versioned::VersionedTransaction;
 use solana_message::{Message, VersionedMessage};
 use solana_system_interface::instruction as system_instruction;
-use spl_token::instruction as token_instruction;
-use spl_token::solana_program::program_pack::Pack;
+use spl_token_2022_interface::extension::{
+    interest_bearing_mint::{self, InterestBearingConfig},
+    metadata_pointer::{self, MetadataPointer},
+    transfer_fee::{self, TransferFeeAmount, TransferFeeConfig},
+    transfer_hook::{self, TransferHook},
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use spl_token_2022_interface::instruction as token_instruction;
+use spl_token_2022_interface::state::{Account as TokenAccount, Mint};
+use spl_token_metadata_interface::state::TokenMetadata;
 use spl_associated_token_account::{
-    get_associated_token_address,
+    get_associated_token_address_with_program_id,
     instruction::create_associated_token_account,
 };
+use spl_transfer_hook_interface::offchain::{add_extra_account_metas_for_execute, AccountFetchError};
+use spl_transfer_hook_interface::solana_instruction::{
+    AccountMeta as HookAccountMeta,
+    Instruction as HookInstruction,
+};
+use spl_transfer_hook_interface::solana_pubkey::Pubkey as HookPubkey;
 use std::str::FromStr;
 
 use crate::models::token::*;
+use crate::models::Fork;
+use crate::services::{load_accounts, SharedFork};
 use crate::state::AppState;
+use crate::utils::{is_token_program, token_extension_types, unpack_mint, unpack_token_account};
 
 /// Helper function to resolve fork_id from user_id
 fn resolve_fork_id(
@@ -25,13 +44,187 @@ fn resolve_fork_id(
 ) -> Result<String, StatusCode> {
     manager
         .get_user_fork_id(user_id)
-        .cloned()
         .ok_or(StatusCode::NOT_FOUND)
 }
 
 // Helper function to parse base58 keypair
 fn parse_keypair(keypair_str: &str) -> Result<Keypair, String> {
-    Ok(Keypair::from_base58_string(keypair_str))
+    let bytes = bs58::decode(keypair_str)
+        .into_vec()
+        .map_err(|e| format!("Invalid keypair: {}", e))?;
+    Keypair::try_from(&bytes[..]).map_err(|e| format!("Invalid keypair: {}", e))
+}
+
+// Helper function to parse an optional token program id, which must be
+// SPL Token or Token-2022
+fn parse_token_program(token_program_id: Option<&str>) -> Result<Option<Pubkey>, (StatusCode, String)> {
+    let Some(token_program_id) = token_program_id else {
+        return Ok(None);
+    };
+
+    let program_id = Pubkey::from_str(token_program_id)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid token program id: {}", e)))?;
+    if !is_token_program(&program_id) {
+        return Err((StatusCode::BAD_REQUEST, format!("{} is not a token program", program_id)));
+    }
+
+    Ok(Some(program_id))
+}
+
+// Helper function to pick the token program of an existing mint or token
+// account, rejecting a requested program that does not own it
+fn resolve_token_program(
+    token_program_id: Option<&str>,
+    account: &Account,
+) -> Result<Pubkey, (StatusCode, String)> {
+    match parse_token_program(token_program_id)? {
+        Some(program_id) if program_id != account.owner => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Account is owned by {}, not {}", account.owner, program_id),
+        )),
+        _ => Ok(account.owner),
+    }
+}
+
+// Helper function to lazy load accounts before the fork is locked
+async fn load_fork_accounts(fork: &SharedFork, addresses: &[Pubkey]) -> Result<(), (StatusCode, String)> {
+    load_accounts(fork, addresses)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+// Helper function to load a mint and decode its base state
+fn load_mint(fork: &Fork, mint: &Pubkey) -> Result<(Account, Mint), (StatusCode, String)> {
+    let account = fork.get_account(mint)
+        .ok_or((StatusCode::NOT_FOUND, "Mint not found".to_string()))?;
+    let state = unpack_mint(&account)
+        .ok_or((StatusCode::BAD_REQUEST, format!("{} is not a token mint", mint)))?;
+    Ok((account, state))
+}
+
+// Helper function to build the accounts a transfer hook needs appended to
+// transfer_checked: the extra accounts resolved from the hook's validation
+// account, then the hook program and the validation account
+async fn transfer_hook_accounts(
+    fork: &SharedFork,
+    transfer_ix: &Instruction,
+    mint_account: &Account,
+    amount: u64,
+) -> Result<Vec<AccountMeta>, String> {
+    let Some(hook_program_id) = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+        .ok()
+        .and_then(|mint| mint.get_extension::<TransferHook>().ok().copied())
+        .and_then(|hook| Option::<Pubkey>::from(hook.program_id))
+    else {
+        return Ok(Vec::new());
+    };
+
+    // The transfer hook interface is built on an older SDK, so keys cross
+    // the boundary as raw bytes
+    let to_hook = |pubkey: &Pubkey| HookPubkey::new_from_array(pubkey.to_bytes());
+    let [source, mint, destination, authority] =
+        [0, 1, 2, 3].map(|index| to_hook(&transfer_ix.accounts[index].pubkey));
+    let mut hook_ix = HookInstruction::new_with_bytes(
+        to_hook(&transfer_ix.program_id),
+        &transfer_ix.data,
+        transfer_ix.accounts.iter()
+            .map(|meta| HookAccountMeta {
+                pubkey: to_hook(&meta.pubkey),
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+    );
+
+    // Extra accounts may be derived from the data of other accounts, which
+    // are loaded lazily from the fork
+    add_extra_account_metas_for_execute(
+        &mut hook_ix,
+        &to_hook(&hook_program_id),
+        &source,
+        &mint,
+        &destination,
+        &authority,
+        amount,
+        |address| {
+            let fork = fork.clone();
+            let address = Pubkey::new_from_array(address.to_bytes());
+            async move {
+                load_accounts(&fork, &[address]).await?;
+                let data = fork.read().await
+                    .get_account(&address)
+                    .map(|account| account.data);
+                Ok::<_, AccountFetchError>(data)
+            }
+        },
+    )
+    .await
+    .map_err(|e| format!("Failed to resolve transfer hook accounts: {}", e))?;
+
+    Ok(hook_ix.accounts[transfer_ix.accounts.len()..]
+        .iter()
+        .map(|meta| AccountMeta {
+            pubkey: Pubkey::new_from_array(meta.pubkey.to_bytes()),
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+        })
+        .collect())
+}
+
+// Helper function to decode the Token-2022 extensions of a token account
+// and its mint
+fn decode_token_extensions(fork: &Fork, account: &Account, mint_account: Option<&Account>) -> TokenExtensionsInfo {
+    let clock = fork.clock();
+    let token_account = StateWithExtensions::<TokenAccount>::unpack(&account.data).ok();
+    let mint = mint_account.and_then(|mint| StateWithExtensions::<Mint>::unpack(&mint.data).ok());
+
+    let transfer_fee = mint.as_ref()
+        .and_then(|mint| mint.get_extension::<TransferFeeConfig>().ok())
+        .map(|config| {
+            let fee = config.get_epoch_fee(clock.epoch);
+            TransferFeeInfo {
+                basis_points: fee.transfer_fee_basis_points.into(),
+                maximum_fee: fee.maximum_fee.into(),
+                withheld_amount: token_account.as_ref()
+                    .and_then(|account| account.get_extension::<TransferFeeAmount>().ok())
+                    .map_or(0, |amount| amount.withheld_amount.into()),
+            }
+        });
+
+    let interest_bearing = mint.as_ref()
+        .and_then(|mint| mint.get_extension::<InterestBearingConfig>().ok().map(|config| (mint.base, config)))
+        .map(|(base, config)| InterestBearingInfo {
+            rate: config.current_rate.into(),
+            ui_amount: token_account.as_ref().and_then(|account| {
+                config.amount_to_ui_amount(account.base.amount, base.decimals, clock.unix_timestamp)
+            }),
+        });
+
+    let extension_names = |types: Vec<ExtensionType>| {
+        types.iter().map(|extension| format!("{:?}", extension)).collect()
+    };
+
+    TokenExtensionsInfo {
+        account_extensions: extension_names(token_extension_types::<TokenAccount>(account)),
+        mint_extensions: mint_account.map_or_else(Vec::new, |mint| extension_names(token_extension_types::<Mint>(mint))),
+        transfer_fee,
+        interest_bearing,
+        metadata_pointer: mint.as_ref()
+            .and_then(|mint| mint.get_extension::<MetadataPointer>().ok())
+            .and_then(|pointer| Option::<Pubkey>::from(pointer.metadata_address))
+            .map(|address| address.to_string()),
+        metadata: mint.as_ref()
+            .and_then(|mint| mint.get_variable_len_extension::<TokenMetadata>().ok())
+            .map(|metadata| TokenMetadataInfo {
+                name: metadata.name,
+                symbol: metadata.symbol,
+                uri: metadata.uri,
+            }),
+        transfer_hook_program_id: mint.as_ref()
+            .and_then(|mint| mint.get_extension::<TransferHook>().ok())
+            .and_then(|hook| Option::<Pubkey>::from(hook.program_id))
+            .map(|program_id| program_id.to_string()),
+    }
 }
 
 // Create a new token mint
@@ -39,49 +232,116 @@ pub async fn create_token_mint(
     State(state): State<AppState>,
     Json(payload): Json<CreateTokenRequest>,
 ) -> Result<Json<CreateTokenResponse>, (StatusCode, String)> {
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
-    
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
-        .map_err(|e| (e, "Fork not found".to_string()))?;
-    
-    let fork = fork_manager.get_fork_mut(&fork_id)
-        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
     // Parse payer keypair
     let payer = parse_keypair(&payload.payer_keypair)
         .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
 
+    let token_program_id = parse_token_program(payload.token_program_id.as_deref())?
+        .unwrap_or_else(spl_token::id);
+
+    let metadata_address = payload.metadata_address.as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid metadata address: {}", e)))?;
+
+    let transfer_hook_program_id = payload.transfer_hook_program_id.as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid transfer hook program id: {}", e)))?;
+
     // Generate new mint keypair
     let mint_keypair = Keypair::new();
+    let mint = mint_keypair.pubkey();
+
+    // Extensions are initialized before the mint itself
+    let mut extension_types = Vec::new();
+    let mut extension_ixs = Vec::new();
+    if let Some(fee) = &payload.transfer_fee {
+        extension_types.push(ExtensionType::TransferFeeConfig);
+        extension_ixs.push(transfer_fee::instruction::initialize_transfer_fee_config(
+            &token_program_id,
+            &mint,
+            Some(&payer.pubkey()),
+            Some(&payer.pubkey()),
+            fee.basis_points,
+            fee.maximum_fee,
+        ));
+    }
+    if let Some(rate) = payload.interest_rate {
+        extension_types.push(ExtensionType::InterestBearingConfig);
+        extension_ixs.push(interest_bearing_mint::instruction::initialize(
+            &token_program_id,
+            &mint,
+            Some(payer.pubkey()),
+            rate,
+        ));
+    }
+    if metadata_address.is_some() {
+        extension_types.push(ExtensionType::MetadataPointer);
+        extension_ixs.push(metadata_pointer::instruction::initialize(
+            &token_program_id,
+            &mint,
+            Some(payer.pubkey()),
+            metadata_address,
+        ));
+    }
+    if transfer_hook_program_id.is_some() {
+        extension_types.push(ExtensionType::TransferHook);
+        extension_ixs.push(transfer_hook::instruction::initialize(
+            &token_program_id,
+            &mint,
+            Some(payer.pubkey()),
+            transfer_hook_program_id,
+        ));
+    }
+    if !extension_types.is_empty() && token_program_id != spl_token_2022_interface::id() {
+        return Err((StatusCode::BAD_REQUEST, "Mint extensions require the Token-2022 program".to_string()));
+    }
+    let mut instructions = extension_ixs.into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:?}", e)))?;
+    
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
     
+    let fork = fork_manager.get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    load_fork_accounts(&fork, &[payer.pubkey()]).await?;
+    let mut fork = fork.write().await;
+
     // Get recent blockhash
-    let blockhash = fork.svm.latest_blockhash();
+    let blockhash = fork.latest_blockhash();
 
-    // Calculate rent for mint account
-    let rent = fork.svm.minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN);
+    // Calculate space and rent for mint account
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&extension_types)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:?}", e)))?;
+    let rent = fork.svm.minimum_balance_for_rent_exemption(space);
 
     // Create mint account instruction
     let create_account_ix = system_instruction::create_account(
         &payer.pubkey(),
-        &mint_keypair.pubkey(),
+        &mint,
         rent,
-        spl_token::state::Mint::LEN as u64,
-        &spl_token::id(),
+        space as u64,
+        &token_program_id,
     );
+    instructions.insert(0, create_account_ix);
 
     // Initialize mint instruction
     let init_mint_ix = token_instruction::initialize_mint(
-        &spl_token::id(),
-        &mint_keypair.pubkey(),
+        &token_program_id,
+        &mint,
         &payer.pubkey(),
         None,
         payload.decimals,
     ).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
+    instructions.push(init_mint_ix);
 
     // Create and sign transaction
     let msg = Message::new_with_blockhash(
-        &[create_account_ix, init_mint_ix],
+        &instructions,
         Some(&payer.pubkey()),
         &blockhash,
     );
@@ -90,14 +350,16 @@ pub async fn create_token_mint(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
 
     // Send transaction
-    let result = fork.svm.send_transaction(tx);
+    let result = fork.send_transaction(tx, false)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
     
-    match result {
-        Ok(signature) => Ok(Json(CreateTokenResponse {
-            mint_address: mint_keypair.pubkey().to_string(),
-            signature: format!("{:?}", signature),
+    match result.error {
+        None => Ok(Json(CreateTokenResponse {
+            mint_address: mint.to_string(),
+            token_program_id: token_program_id.to_string(),
+            signature: result.signature,
         })),
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e))),
+        Some(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
     }
 }
 
@@ -106,14 +368,7 @@ pub async fn create_token_account(
     State(state): State<AppState>,
     Json(payload): Json<CreateTokenAccountRequest>,
 ) -> Result<Json<CreateTokenAccountResponse>, (StatusCode, String)> {
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
-    
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
-        .map_err(|e| (e, "Fork not found".to_string()))?;
-    
-    let fork = fork_manager.get_fork_mut(&fork_id)
-        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
     // Parse inputs
     let payer = parse_keypair(&payload.payer_keypair)
@@ -124,20 +379,33 @@ pub async fn create_token_account(
     
     let owner = Pubkey::from_str(&payload.owner_address)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid owner address: {}", e)))?;
+    
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+    
+    let fork = fork_manager.get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    load_fork_accounts(&fork, &[payer.pubkey(), mint, owner]).await?;
+
+    let (mint_account, _) = load_mint(&*fork.read().await, &mint)?;
+    let token_program_id = resolve_token_program(payload.token_program_id.as_deref(), &mint_account)?;
 
     // Get associated token address
-    let token_account = get_associated_token_address(&owner, &mint);
+    let token_account = get_associated_token_address_with_program_id(&owner, &mint, &token_program_id);
+    load_fork_accounts(&fork, &[token_account]).await?;
+    let mut fork = fork.write().await;
+
 
     // Create associated token account instruction
     let create_ata_ix = create_associated_token_account(
         &payer.pubkey(),
         &owner,
         &mint,
-        &spl_token::id(),
+        &token_program_id,
     );
 
     // Get recent blockhash
-    let blockhash = fork.svm.latest_blockhash();
+    let blockhash = fork.latest_blockhash();
 
     // Create and sign transaction
     let msg = Message::new_with_blockhash(
@@ -150,14 +418,16 @@ pub async fn create_token_account(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
 
     // Send transaction
-    let result = fork.svm.send_transaction(tx);
+    let result = fork.send_transaction(tx, false)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
     
-    match result {
-        Ok(signature) => Ok(Json(CreateTokenAccountResponse {
+    match result.error {
+        None => Ok(Json(CreateTokenAccountResponse {
             token_account: token_account.to_string(),
-            signature: format!("{:?}", signature),
+            token_program_id: token_program_id.to_string(),
+            signature: result.signature,
         })),
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e))),
+        Some(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
     }
 }
 
@@ -166,14 +436,7 @@ pub async fn mint_tokens(
     State(state): State<AppState>,
     Json(payload): Json<MintTokensRequest>,
 ) -> Result<Json<MintTokensResponse>, (StatusCode, String)> {
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
-    
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
-        .map_err(|e| (e, "Fork not found".to_string()))?;
-    
-    let fork = fork_manager.get_fork_mut(&fork_id)
-        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
     // Parse inputs
     let mint_authority = parse_keypair(&payload.mint_authority_keypair)
@@ -184,19 +447,31 @@ pub async fn mint_tokens(
     
     let destination = Pubkey::from_str(&payload.destination_account)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid destination: {}", e)))?;
+    
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+    
+    let fork = fork_manager.get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    load_fork_accounts(&fork, &[mint_authority.pubkey(), mint, destination]).await?;
+    let mut fork = fork.write().await;
+
+    let (mint_account, mint_state) = load_mint(&fork, &mint)?;
+    let token_program_id = resolve_token_program(payload.token_program_id.as_deref(), &mint_account)?;
 
     // Create mint to instruction
-    let mint_to_ix = token_instruction::mint_to(
-        &spl_token::id(),
+    let mint_to_ix = token_instruction::mint_to_checked(
+        &token_program_id,
         &mint,
         &destination,
         &mint_authority.pubkey(),
         &[],
         payload.amount,
+        mint_state.decimals,
     ).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
 
     // Get recent blockhash
-    let blockhash = fork.svm.latest_blockhash();
+    let blockhash = fork.latest_blockhash();
 
     // Create and sign transaction
     let msg = Message::new_with_blockhash(
@@ -209,23 +484,24 @@ pub async fn mint_tokens(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
 
     // Send transaction
-    let result = fork.svm.send_transaction(tx);
+    let result = fork.send_transaction(tx, false)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
     
-    match result {
-        Ok(signature) => {
+    match result.error {
+        None => {
             // Get new balance
             let account = fork.svm.get_account(&destination)
                 .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Failed to get account".to_string()))?;
             
-            let token_account = spl_token::state::Account::unpack(&account.data)
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
+            let token_account = unpack_token_account(&account)
+                .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse token account".to_string()))?;
 
             Ok(Json(MintTokensResponse {
-                signature: format!("{:?}", signature),
+                signature: result.signature,
                 new_balance: token_account.amount,
             }))
         },
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e))),
+        Some(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
     }
 }
 
@@ -234,14 +510,7 @@ pub async fn transfer_tokens(
     State(state): State<AppState>,
     Json(payload): Json<TransferTokensRequest>,
 ) -> Result<Json<TransferTokensResponse>, (StatusCode, String)> {
-    let mut fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
-    
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
-        .map_err(|e| (e, "Fork not found".to_string()))?;
-    
-    let fork = fork_manager.get_fork_mut(&fork_id)
-        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    let fork_manager = &state.fork_manager;
 
     // Parse inputs
     let owner = parse_keypair(&payload.from_keypair)
@@ -252,19 +521,53 @@ pub async fn transfer_tokens(
     
     let destination = Pubkey::from_str(&payload.destination_account)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid destination: {}", e)))?;
+    
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+    
+    let fork = fork_manager.get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+    load_fork_accounts(&fork, &[owner.pubkey(), source, destination]).await?;
+
+    // transfer_checked needs the mint and its decimals, taken from the source
+    let source_account = fork.read().await
+        .get_account(&source)
+        .ok_or((StatusCode::NOT_FOUND, "Source account not found".to_string()))?;
+    let source_token = unpack_token_account(&source_account)
+        .ok_or((StatusCode::BAD_REQUEST, format!("{} is not a token account", source)))?;
+    let token_program_id = resolve_token_program(payload.token_program_id.as_deref(), &source_account)?;
+    let mint = source_token.mint;
+    load_fork_accounts(&fork, &[mint]).await?;
+    let (mint_account, mint_state) = load_mint(&*fork.read().await, &mint)?;
 
     // Create transfer instruction
-    let transfer_ix = token_instruction::transfer(
-        &spl_token::id(),
+    let mut transfer_ix = token_instruction::transfer_checked(
+        &token_program_id,
         &source,
+        &mint,
         &destination,
         &owner.pubkey(),
         &[],
         payload.amount,
+        mint_state.decimals,
     ).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
+    let hook_accounts = transfer_hook_accounts(&fork, &transfer_ix, &mint_account, payload.amount)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    transfer_ix.accounts.extend(hook_accounts);
+    let transfer_accounts: Vec<Pubkey> = transfer_ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    load_fork_accounts(&fork, &transfer_accounts).await?;
+    let mut fork = fork.write().await;
+
+    // Fee the Token-2022 program will withhold for this transfer
+    let fee = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+        .ok()
+        .and_then(|mint| mint.get_extension::<TransferFeeConfig>().ok().copied())
+        .and_then(|config| config.calculate_epoch_fee(fork.clock().epoch, payload.amount))
+        .unwrap_or(0);
 
     // Get recent blockhash
-    let blockhash = fork.svm.latest_blockhash();
+    let blockhash = fork.latest_blockhash();
 
     // Create and sign transaction
     let msg = Message::new_with_blockhash(
@@ -277,28 +580,30 @@ pub async fn transfer_tokens(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
 
     // Send transaction
-    let result = fork.svm.send_transaction(tx);
+    let result = fork.send_transaction(tx, false)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
     
-    match result {
-        Ok(signature) => {
+    match result.error {
+        None => {
             // Get balances
             let source_account = fork.svm.get_account(&source)
                 .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Failed to get source account".to_string()))?;
             let dest_account = fork.svm.get_account(&destination)
                 .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Failed to get dest account".to_string()))?;
             
-            let source_token = spl_token::state::Account::unpack(&source_account.data)
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
-            let dest_token = spl_token::state::Account::unpack(&dest_account.data)
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)))?;
+            let source_token = unpack_token_account(&source_account)
+                .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse source account".to_string()))?;
+            let dest_token = unpack_token_account(&dest_account)
+                .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse dest account".to_string()))?;
 
             Ok(Json(TransferTokensResponse {
-                signature: format!("{:?}", signature),
+                signature: result.signature,
                 source_balance: source_token.amount,
                 destination_balance: dest_token.amount,
+                fee,
             }))
         },
-        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e))),
+        Some(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
     }
 }
 
@@ -307,10 +612,9 @@ pub async fn get_token_balance(
     State(state): State<AppState>,
     Json(payload): Json<GetTokenBalanceRequest>,
 ) -> Result<Json<GetTokenBalanceResponse>, (StatusCode, String)> {
-    let fork_manager = state.fork_manager.lock()
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Lock error".to_string()))?;
+    let fork_manager = &state.fork_manager;
     
-    let fork_id = resolve_fork_id(&fork_manager, &payload.user_id)
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
         .map_err(|e| (e, "Fork not found".to_string()))?;
     
     let fork = fork_manager.get_fork(&fork_id)
@@ -319,16 +623,81 @@ pub async fn get_token_balance(
     let token_account_pubkey = Pubkey::from_str(&payload.token_account)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid token account: {}", e)))?;
 
-    let account = fork.svm.get_account(&token_account_pubkey)
+    load_fork_accounts(&fork, &[token_account_pubkey]).await?;
+    let account = fork.read().await
+        .get_account(&token_account_pubkey)
         .ok_or((StatusCode::NOT_FOUND, "Token account not found".to_string()))?;
 
-    let token_account = spl_token::state::Account::unpack(&account.data)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to parse token account: {:?}", e)))?;
+    let token_program_id = resolve_token_program(payload.token_program_id.as_deref(), &account)?;
+    let token_account = unpack_token_account(&account)
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Failed to parse token account".to_string()))?;
+
+    load_fork_accounts(&fork, &[token_account.mint]).await?;
+    let fork = fork.read().await;
+    let mint_account = fork.get_account(&token_account.mint);
+    let decimals = mint_account.as_ref()
+        .and_then(unpack_mint)
+        .map_or(0, |mint| mint.decimals);
+    let extensions = (token_program_id == spl_token_2022_interface::id())
+        .then(|| decode_token_extensions(&fork, &account, mint_account.as_ref()));
 
     Ok(Json(GetTokenBalanceResponse {
         token_account: payload.token_account,
         balance: token_account.amount,
         mint: token_account.mint.to_string(),
         owner: token_account.owner.to_string(),
+        token_program_id: token_program_id.to_string(),
+        decimals,
+        extensions,
     }))
-}
\ No newline at end of file
+}
+
+// Set a token account's balance directly, without the mint authority
+pub async fn set_token_balance(
+    State(state): State<AppState>,
+    Json(payload): Json<SetTokenBalanceRequest>,
+) -> Result<Json<SetTokenBalanceResponse>, (StatusCode, String)> {
+    let fork_manager = &state.fork_manager;
+    
+    let fork_id = resolve_fork_id(fork_manager, &payload.user_id)
+        .map_err(|e| (e, "Fork not found".to_string()))?;
+    
+    let fork = fork_manager.get_fork(&fork_id)
+        .ok_or((StatusCode::NOT_FOUND, "Fork not found".to_string()))?;
+
+    // Parse inputs
+    let mint = Pubkey::from_str(&payload.mint_address)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid mint address: {}", e)))?;
+    
+    let owner = payload.owner_address.as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid owner address: {}", e)))?;
+    
+    let token_account = payload.token_account.as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid token account: {}", e)))?;
+
+    load_fork_accounts(&fork, &[mint]).await?;
+    let (mint_account, _) = load_mint(&*fork.read().await, &mint)?;
+    let token_program_id = resolve_token_program(payload.token_program_id.as_deref(), &mint_account)?;
+
+    // The token account is either given or the owner's associated token account
+    if let Some(address) = token_account.or_else(|| {
+        owner.map(|owner| get_associated_token_address_with_program_id(&owner, &mint, &token_program_id))
+    }) {
+        load_fork_accounts(&fork, &[address]).await?;
+    }
+    let mut fork = fork.write().await;
+
+    let response = fork.set_token_balance(
+        mint,
+        token_account,
+        owner,
+        payload.amount,
+        payload.adjust_supply.unwrap_or(false),
+    ).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    Ok(Json(response))
+}
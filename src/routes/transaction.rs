@@ -1,45 +1,31 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::StatusCode,
     Json,
 };
+use serde::Deserialize;
 
 use solana_message::Message;
 use solana_pubkey::Pubkey;
 use solana_signer::Signer;
 use solana_system_interface::instruction::transfer;
-use solana_transaction::Transaction;
+use solana_transaction::{versioned::VersionedTransaction, Transaction};
 use std::str::FromStr;
 
 use crate::{
     models::{
         SendTransactionRequest,
         SendTransactionResponse,
+        TransactionListResponse,
+        TransactionSummary,
         TransferRequest,
         TransferResponse,
     },
+    services::{load_accounts, load_transaction_accounts},
     state::AppState,
-    utils::{parse_keypair, sol_to_lamports},
+    utils::{parse_keypair, resolve_fork_or_user_id, sol_to_lamports},
 };
 
-/// Helper function to resolve fork_id from request
-fn resolve_fork_id(
-    manager: &crate::services::fork_manager::ForkManager,
-    fork_id: Option<String>,
-    user_id: Option<String>,
-) -> Result<String, StatusCode> {
-    if let Some(fid) = fork_id {
-        Ok(fid)
-    } else if let Some(uid) = user_id {
-        manager
-            .get_user_fork_id(&uid)
-            .cloned()
-            .ok_or(StatusCode::NOT_FOUND)
-    } else {
-        Err(StatusCode::BAD_REQUEST)
-    }
-}
-
 /// Send a raw transaction
 pub async fn send_transaction(
     State(state): State<AppState>,
@@ -52,29 +38,31 @@ pub async fn send_transaction(
         .decode(&payload.transaction)
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    // Deserialize transaction
-    let transaction: Transaction = bincode::deserialize(&tx_bytes)
+    // Deserialize transaction; legacy and v0 messages share the versioned wire format
+    let transaction: VersionedTransaction = bincode::deserialize(&tx_bytes)
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let mut manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
-    let fork_id = resolve_fork_id(&manager, payload.fork_id, payload.user_id)?;
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, payload.user_id)?;
 
     let fork = manager
-        .get_fork_mut(&fork_id)
+        .get_fork(&fork_id)
         .ok_or(StatusCode::NOT_FOUND)?;
+    load_transaction_accounts(&fork, &transaction)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut fork = fork.write().await;
 
     let result = fork
-        .send_transaction(transaction)
+        .send_transaction(transaction, payload.include_diff.unwrap_or(false))
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(SendTransactionResponse {
         success: result.success,
         signature: result.signature,
         error: result.error,
+        account_diffs: result.account_diffs,
     }))
 }
 
@@ -98,22 +86,23 @@ pub async fn transfer_sol(
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let mut manager = state
-        .fork_manager
-        .lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manager = &state.fork_manager;
 
-    let fork_id = resolve_fork_id(&manager, payload.fork_id, payload.user_id)?;
+    let fork_id = resolve_fork_or_user_id(manager, payload.fork_id, payload.user_id)?;
 
     let fork = manager
-        .get_fork_mut(&fork_id)
+        .get_fork(&fork_id)
         .ok_or(StatusCode::NOT_FOUND)?;
+    load_accounts(&fork, &[from_pubkey, to_pubkey])
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut fork = fork.write().await;
 
     // Create transfer instruction
     let instruction = transfer(&from_pubkey, &to_pubkey, lamports);
 
     // Get latest blockhash from fork
-    let recent_blockhash = fork.svm.latest_blockhash();
+    let recent_blockhash = fork.latest_blockhash();
 
     // Create message
     let message = Message::new(&[instruction], Some(&from_pubkey));
@@ -123,7 +112,7 @@ pub async fn transfer_sol(
 
     // Send transaction
     let result = fork
-        .send_transaction(transaction)
+        .send_transaction(transaction, payload.include_diff.unwrap_or(false))
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(TransferResponse {
@@ -134,5 +123,54 @@ pub async fn transfer_sol(
         amount_sol: payload.amount_sol,
         amount_lamports: lamports,
         error: result.error,
+        account_diffs: result.account_diffs,
     }))
-}
\ No newline at end of file
+}
+
+/// Default number of transactions returned by `list_transactions`
+const DEFAULT_TRANSACTION_LIST_LIMIT: usize = 100;
+
+#[derive(Deserialize)]
+pub struct TransactionListQuery {
+    pub fork_id: Option<String>,
+    pub user_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// List a fork's executed transactions, newest first
+pub async fn list_transactions(
+    State(state): State<AppState>,
+    Query(params): Query<TransactionListQuery>,
+) -> Result<Json<TransactionListResponse>, StatusCode> {
+    let manager = &state.fork_manager;
+
+    let fork_id = resolve_fork_or_user_id(manager, params.fork_id, params.user_id)?;
+
+    let fork = manager
+        .get_fork(&fork_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let fork = fork.read().await;
+
+    let transactions = fork
+        .history
+        .iter_newest_first()
+        .take(params.limit.unwrap_or(DEFAULT_TRANSACTION_LIST_LIMIT))
+        .map(|record| TransactionSummary {
+            signature: record.signature.clone(),
+            slot: record.slot,
+            block_time: record.block_time,
+            success: record.err.is_none(),
+            error: record.err.as_ref().map(|e| e.to_string()),
+            fee: record.fee,
+            compute_units_consumed: record.meta.compute_units_consumed,
+            accounts: record.account_keys.iter().map(|key| key.to_string()).collect(),
+            logs: record.meta.logs.clone(),
+        })
+        .collect();
+
+    Ok(Json(TransactionListResponse {
+        fork_id,
+        total: fork.history.len(),
+        transactions,
+    }))
+}
@@ -0,0 +1,524 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use solana_account::Account;
+use solana_pubkey::Pubkey;
+
+use crate::config::AccountCacheSettings;
+use crate::models::AccountState;
+
+/// Version of the account cache file format
+const ACCOUNT_CACHE_VERSION: u32 = 1;
+
+/// Shared cache of accounts fetched from upstream RPCs, so hot accounts
+/// (mints, programs, pools) are fetched once for all forks.
+///
+/// Entries are keyed by endpoint, pubkey and the slot the upstream node
+/// reported for the fetch, and include accounts that did not exist. A
+/// lookup pinned to a slot is only served by an entry from that slot or a
+/// later one; an unpinned lookup takes the most recent entry. Entries older
+/// than the configured max age are never served, and the least recently
+/// used entries are evicted once the cache is full.
+pub struct AccountCache {
+    settings: AccountCacheSettings,
+    entries: Mutex<CacheEntries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
+}
+
+/// A cached upstream account, or the fact that it did not exist
+struct CachedAccount {
+    account: Option<Account>,
+    /// Unix time the account was fetched at, in seconds
+    fetched_at: u64,
+    /// Position in the recency order, higher is more recently used
+    last_used: u64,
+}
+
+/// Cache entries and their recency order, guarded by the cache lock
+#[derive(Default)]
+struct CacheEntries {
+    /// Endpoint -> pubkey -> slot -> account
+    upstreams: HashMap<Arc<str>, HashMap<Pubkey, BTreeMap<u64, CachedAccount>>>,
+    /// `last_used` -> entry, least recently used first
+    recency: BTreeMap<u64, (Arc<str>, Pubkey, u64)>,
+    next_use: u64,
+    len: usize,
+}
+
+/// Cache counters, as reported by the admin endpoint
+#[derive(Serialize)]
+pub struct AccountCacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    pub max_entries: usize,
+    /// Seconds before an entry is fetched again; `None` keeps entries until evicted
+    pub max_age_seconds: Option<u64>,
+    pub hits: u64,
+    pub misses: u64,
+    /// Share of lookups served from the cache, 0 before the first lookup
+    pub hit_rate: f64,
+    /// Entries removed to make room for new ones
+    pub evictions: u64,
+    /// Entries removed for being older than the max age
+    pub expirations: u64,
+    /// Backing file, if the cache is saved to disk
+    pub path: Option<String>,
+}
+
+/// On-disk form of the cache
+#[derive(Serialize, Deserialize)]
+struct AccountCacheFile {
+    version: u32,
+    entries: Vec<AccountCacheFileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AccountCacheFileEntry {
+    endpoint: String,
+    address: String,
+    slot: u64,
+    fetched_at: u64,
+    /// `None` for an account that did not exist
+    account: Option<AccountState>,
+}
+
+impl AccountCache {
+    pub fn new(settings: AccountCacheSettings) -> Self {
+        Self {
+            settings,
+            entries: Mutex::new(CacheEntries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            expirations: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether the cache keeps any entries at all
+    pub fn is_enabled(&self) -> bool {
+        self.settings.max_entries > 0
+    }
+
+    /// Look `pubkeys` up for `endpoint`. Each result is `None` on a miss and
+    /// `Some(account)` on a hit, where `account` is `None` if it did not exist.
+    pub fn get_many(
+        &self,
+        endpoint: &str,
+        pubkeys: &[Pubkey],
+        min_slot: Option<u64>,
+    ) -> Vec<Option<Option<Account>>> {
+        if !self.is_enabled() {
+            return vec![None; pubkeys.len()];
+        }
+
+        let now = current_timestamp();
+        let mut entries = self.entries();
+
+        let results: Vec<_> = pubkeys
+            .iter()
+            .map(|pubkey| {
+                self.remove_stale(&mut entries, endpoint, pubkey, now);
+                entries.touch(endpoint, pubkey, min_slot)
+            })
+            .collect();
+
+        let hits = results.iter().filter(|result| result.is_some()).count() as u64;
+        self.hits.fetch_add(hits, Ordering::Relaxed);
+        self.misses.fetch_add(results.len() as u64 - hits, Ordering::Relaxed);
+
+        results
+    }
+
+    /// Store accounts `endpoint` returned at `slot`, evicting the least
+    /// recently used entries if the cache is full
+    pub fn insert_many(&self, endpoint: &str, slot: u64, accounts: &[(Pubkey, Option<Account>)]) {
+        self.insert_at(endpoint, slot, current_timestamp(), accounts);
+    }
+
+    /// Remove every entry, returning how many there were
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries();
+        let removed = entries.len;
+        *entries = CacheEntries::default();
+        removed
+    }
+
+    /// Remove entries older than the max age, returning how many were removed
+    pub fn remove_expired(&self) -> usize {
+        let Some(max_age) = self.settings.max_age else {
+            return 0;
+        };
+
+        let oldest = current_timestamp().saturating_sub(max_age.as_secs());
+        let mut entries = self.entries();
+        let stale: Vec<_> = entries
+            .iter()
+            .filter(|(_, _, _, cached)| cached.fetched_at < oldest)
+            .map(|(endpoint, pubkey, slot, _)| (endpoint.clone(), *pubkey, slot))
+            .collect();
+
+        for (endpoint, pubkey, slot) in &stale {
+            entries.remove(endpoint, pubkey, *slot);
+        }
+        self.expirations.fetch_add(stale.len() as u64, Ordering::Relaxed);
+
+        stale.len()
+    }
+
+    pub fn stats(&self) -> AccountCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+
+        AccountCacheStats {
+            enabled: self.is_enabled(),
+            entries: self.entries().len,
+            max_entries: self.settings.max_entries,
+            max_age_seconds: self.settings.max_age.map(|max_age| max_age.as_secs()),
+            hits,
+            misses,
+            hit_rate: if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 },
+            evictions: self.evictions.load(Ordering::Relaxed),
+            expirations: self.expirations.load(Ordering::Relaxed),
+            path: (!self.settings.path.is_empty()).then(|| self.settings.path.clone()),
+        }
+    }
+
+    /// Load the backing file, if one is configured and exists, skipping
+    /// entries that are already stale. Returns the number of entries loaded.
+    pub fn load(&self) -> Result<usize, String> {
+        let path = Path::new(&self.settings.path);
+        if self.settings.path.is_empty() || !self.is_enabled() || !path.exists() {
+            return Ok(0);
+        }
+
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file: AccountCacheFile = serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        if file.version != ACCOUNT_CACHE_VERSION {
+            return Err(format!(
+                "Unsupported account cache version {} in {}",
+                file.version,
+                path.display()
+            ));
+        }
+
+        let oldest = self
+            .settings
+            .max_age
+            .map_or(0, |max_age| current_timestamp().saturating_sub(max_age.as_secs()));
+
+        // Insert oldest first, so the newest entries survive if the file holds more than fit
+        let mut file_entries: Vec<_> = file.entries.into_iter().filter(|entry| entry.fetched_at >= oldest).collect();
+        file_entries.sort_by_key(|entry| entry.fetched_at);
+
+        for entry in file_entries {
+            let pubkey = match entry.address.parse::<Pubkey>() {
+                Ok(pubkey) => pubkey,
+                Err(e) => {
+                    eprintln!("Warning: Skipping cached account {}: {}", entry.address, e);
+                    continue;
+                }
+            };
+            let account = match entry.account.as_ref().map(AccountState::to_account).transpose() {
+                Ok(account) => account.map(|(_, account)| account),
+                Err(e) => {
+                    eprintln!("Warning: Skipping cached account {}: {}", entry.address, e);
+                    continue;
+                }
+            };
+
+            self.insert_at(&entry.endpoint, entry.slot, entry.fetched_at, &[(pubkey, account)]);
+        }
+
+        Ok(self.entries().len)
+    }
+
+    /// Write every entry to the backing file, if one is configured, replacing
+    /// the previous file atomically. Returns the number of entries written.
+    pub fn persist(&self) -> Result<usize, String> {
+        if self.settings.path.is_empty() || !self.is_enabled() {
+            return Ok(0);
+        }
+
+        let file = {
+            let entries = self.entries();
+            AccountCacheFile {
+                version: ACCOUNT_CACHE_VERSION,
+                entries: entries
+                    .iter()
+                    .map(|(endpoint, pubkey, slot, cached)| AccountCacheFileEntry {
+                        endpoint: endpoint.to_string(),
+                        address: pubkey.to_string(),
+                        slot,
+                        fetched_at: cached.fetched_at,
+                        account: cached.account.as_ref().map(|account| AccountState::from_account(pubkey, account)),
+                    })
+                    .collect(),
+            }
+        };
+
+        let path = Path::new(&self.settings.path);
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
+
+        let tmp_path = path.with_extension("json.tmp");
+        let bytes = serde_json::to_vec(&file).map_err(|e| format!("Failed to serialize account cache: {}", e))?;
+
+        fs::write(&tmp_path, bytes).map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        Ok(file.entries.len())
+    }
+
+    fn insert_at(&self, endpoint: &str, slot: u64, fetched_at: u64, accounts: &[(Pubkey, Option<Account>)]) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut entries = self.entries();
+        for (pubkey, account) in accounts {
+            entries.insert(endpoint, *pubkey, slot, fetched_at, account.clone());
+        }
+
+        let mut evicted = 0;
+        while entries.len > self.settings.max_entries {
+            let Some((_, (endpoint, pubkey, slot))) = entries.recency.pop_first() else {
+                break;
+            };
+            entries.remove(&endpoint, &pubkey, slot);
+            evicted += 1;
+        }
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
+    }
+
+    /// Drop the entries of one account that are older than the max age
+    fn remove_stale(&self, entries: &mut CacheEntries, endpoint: &str, pubkey: &Pubkey, now: u64) {
+        let Some(max_age) = self.settings.max_age else {
+            return;
+        };
+
+        let oldest = now.saturating_sub(max_age.as_secs());
+        let stale: Vec<u64> = entries
+            .slots(endpoint, pubkey)
+            .map(|slots| {
+                slots
+                    .iter()
+                    .filter(|(_, cached)| cached.fetched_at < oldest)
+                    .map(|(slot, _)| *slot)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        for slot in &stale {
+            entries.remove(endpoint, pubkey, *slot);
+        }
+        self.expirations.fetch_add(stale.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Lock the entries. Every change leaves them consistent, so poisoning is ignored.
+    fn entries(&self) -> MutexGuard<'_, CacheEntries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl CacheEntries {
+    fn slots(&self, endpoint: &str, pubkey: &Pubkey) -> Option<&BTreeMap<u64, CachedAccount>> {
+        self.upstreams.get(endpoint)?.get(pubkey)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&Arc<str>, &Pubkey, u64, &CachedAccount)> {
+        self.upstreams.iter().flat_map(|(endpoint, accounts)| {
+            accounts.iter().flat_map(move |(pubkey, slots)| {
+                slots.iter().map(move |(slot, cached)| (endpoint, pubkey, *slot, cached))
+            })
+        })
+    }
+
+    /// Find the entry a lookup is served from and mark it as just used: the
+    /// oldest slot at or after `min_slot` when pinned, the newest otherwise
+    fn touch(&mut self, endpoint: &str, pubkey: &Pubkey, min_slot: Option<u64>) -> Option<Option<Account>> {
+        let next_use = self.next_use;
+        let endpoint = self.upstreams.get_key_value(endpoint)?.0.clone();
+        let slots = self.upstreams.get_mut(&endpoint)?.get_mut(pubkey)?;
+        let (slot, cached) = match min_slot {
+            Some(min_slot) => slots.range_mut(min_slot..).next()?,
+            None => slots.iter_mut().next_back()?,
+        };
+
+        self.recency.remove(&cached.last_used);
+        self.recency.insert(next_use, (endpoint, *pubkey, *slot));
+        cached.last_used = next_use;
+        self.next_use += 1;
+
+        Some(cached.account.clone())
+    }
+
+    fn insert(&mut self, endpoint: &str, pubkey: Pubkey, slot: u64, fetched_at: u64, account: Option<Account>) {
+        let endpoint: Arc<str> = match self.upstreams.get_key_value(endpoint) {
+            Some((endpoint, _)) => endpoint.clone(),
+            None => Arc::from(endpoint),
+        };
+
+        let cached = CachedAccount {
+            account,
+            fetched_at,
+            last_used: self.next_use,
+        };
+        let replaced = self
+            .upstreams
+            .entry(endpoint.clone())
+            .or_default()
+            .entry(pubkey)
+            .or_default()
+            .insert(slot, cached);
+
+        match replaced {
+            Some(replaced) => {
+                self.recency.remove(&replaced.last_used);
+            }
+            None => self.len += 1,
+        }
+        self.recency.insert(self.next_use, (endpoint, pubkey, slot));
+        self.next_use += 1;
+    }
+
+    fn remove(&mut self, endpoint: &str, pubkey: &Pubkey, slot: u64) {
+        let Some(accounts) = self.upstreams.get_mut(endpoint) else {
+            return;
+        };
+        let Some(slots) = accounts.get_mut(pubkey) else {
+            return;
+        };
+        let Some(cached) = slots.remove(&slot) else {
+            return;
+        };
+
+        self.recency.remove(&cached.last_used);
+        self.len -= 1;
+
+        if slots.is_empty() {
+            accounts.remove(pubkey);
+            if accounts.is_empty() {
+                self.upstreams.remove(endpoint);
+            }
+        }
+    }
+}
+
+/// Get current timestamp in seconds
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const ENDPOINT: &str = "http://upstream";
+
+    fn cache(max_entries: usize, max_age: Option<Duration>) -> AccountCache {
+        AccountCache::new(AccountCacheSettings { max_entries, max_age, path: String::new() })
+    }
+
+    fn account(lamports: u64) -> Account {
+        Account { lamports, ..Account::default() }
+    }
+
+    fn lookup(cache: &AccountCache, pubkey: Pubkey, min_slot: Option<u64>) -> Option<Option<Account>> {
+        cache.get_many(ENDPOINT, &[pubkey], min_slot).remove(0)
+    }
+
+    #[test]
+    fn evicts_least_recently_used_first() {
+        let cache = cache(2, None);
+        let [a, b, c] = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+
+        cache.insert_many(ENDPOINT, 1, &[(a, Some(account(1))), (b, Some(account(2)))]);
+        // Using `a` makes `b` the least recently used entry
+        assert!(lookup(&cache, a, None).is_some());
+        cache.insert_many(ENDPOINT, 1, &[(c, Some(account(3)))]);
+
+        assert_eq!(lookup(&cache, a, None), Some(Some(account(1))));
+        assert_eq!(lookup(&cache, b, None), None);
+        assert_eq!(lookup(&cache, c, None), Some(Some(account(3))));
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[test]
+    fn expired_entries_are_misses() {
+        let cache = cache(10, Some(Duration::from_secs(60)));
+        let [fresh, stale] = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        cache.insert_many(ENDPOINT, 1, &[(fresh, Some(account(1)))]);
+        cache.insert_at(ENDPOINT, 1, current_timestamp() - 120, &[(stale, Some(account(2)))]);
+
+        assert_eq!(lookup(&cache, fresh, None), Some(Some(account(1))));
+        assert_eq!(lookup(&cache, stale, None), None);
+        assert_eq!(cache.stats().expirations, 1);
+        assert_eq!(cache.stats().entries, 1);
+    }
+
+    #[test]
+    fn pinned_lookups_need_a_recent_enough_slot() {
+        let cache = cache(10, None);
+        let pubkey = Pubkey::new_unique();
+
+        cache.insert_many(ENDPOINT, 90, &[(pubkey, Some(account(1)))]);
+        cache.insert_many(ENDPOINT, 100, &[(pubkey, Some(account(2)))]);
+
+        assert_eq!(lookup(&cache, pubkey, None), Some(Some(account(2))));
+        assert_eq!(lookup(&cache, pubkey, Some(80)), Some(Some(account(1))));
+        assert_eq!(lookup(&cache, pubkey, Some(95)), Some(Some(account(2))));
+        // The newest cached slot is below min_slot
+        assert_eq!(lookup(&cache, pubkey, Some(101)), None);
+    }
+
+    #[test]
+    fn missing_accounts_are_cached_per_endpoint() {
+        let cache = cache(10, None);
+        let pubkey = Pubkey::new_unique();
+
+        cache.insert_many(ENDPOINT, 1, &[(pubkey, None)]);
+
+        assert_eq!(lookup(&cache, pubkey, None), Some(None));
+        assert_eq!(cache.get_many("http://other", &[pubkey], None), vec![None]);
+    }
+
+    #[test]
+    fn persists_and_loads_entries() {
+        let path = std::env::temp_dir().join(format!("account-cache-{}.json", std::process::id()));
+        let settings = AccountCacheSettings {
+            max_entries: 10,
+            max_age: None,
+            path: path.to_string_lossy().into_owned(),
+        };
+        let [present, missing] = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let cache = AccountCache::new(settings.clone());
+        cache.insert_many(ENDPOINT, 7, &[(present, Some(account(5))), (missing, None)]);
+        assert_eq!(cache.persist().unwrap(), 2);
+
+        let loaded = AccountCache::new(settings);
+        assert_eq!(loaded.load().unwrap(), 2);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(lookup(&loaded, present, Some(7)), Some(Some(account(5))));
+        assert_eq!(lookup(&loaded, missing, Some(7)), Some(None));
+    }
+}
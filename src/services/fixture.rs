@@ -0,0 +1,274 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use futures::future::{self, BoxFuture};
+use serde::{Deserialize, Serialize};
+use solana_account::Account;
+use solana_pubkey::Pubkey;
+use solana_sdk::hash::Hash;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::services::upstream::TOKEN_ACCOUNT_OWNER_OFFSET;
+use crate::services::{AccountsAtSlot, Upstream};
+
+/// Scheme of endpoints served from a fixture directory
+pub const FIXTURE_SCHEME: &str = "file://";
+
+/// File in a fixture directory holding the slot and blockhash; every other
+/// `.json` file is an account dump
+const FIXTURE_UPSTREAM_FILE: &str = "upstream.json";
+
+/// An account dump, in the format written by `solana account --output json`
+#[derive(Serialize, Deserialize)]
+struct AccountDump {
+    pubkey: String,
+    account: DumpedAccount,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpedAccount {
+    lamports: u64,
+    /// Data and its encoding, `base64` or `base58`
+    data: (String, String),
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+    #[serde(default)]
+    space: Option<u64>,
+}
+
+/// Slot and blockhash served by a fixture directory
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct UpstreamDump {
+    #[serde(default)]
+    slot: u64,
+    #[serde(default)]
+    blockhash: Option<String>,
+}
+
+/// Check that the fixture directory of a `file://` endpoint lies under
+/// `root`, so requests cannot make the server read arbitrary directories.
+/// Other endpoints always pass.
+pub fn check_fixture_endpoint(endpoint: &str, root: &str) -> Result<(), String> {
+    let Some(dir) = endpoint.strip_prefix(FIXTURE_SCHEME) else {
+        return Ok(());
+    };
+    if root.is_empty() {
+        return Err(format!("{} endpoints are not allowed in requests", FIXTURE_SCHEME));
+    }
+
+    let root = fs::canonicalize(root).map_err(|e| format!("Invalid fixture root {}: {}", root, e))?;
+    let dir = fs::canonicalize(dir).map_err(|e| format!("Failed to read fixture directory {}: {}", dir, e))?;
+    if !dir.starts_with(&root) {
+        return Err(format!("Fixture directory {} is outside the fixture root", dir.display()));
+    }
+
+    Ok(())
+}
+
+/// Upstream served from a directory of account dumps, so forks can be
+/// created and loaded without network access. Accounts missing from the
+/// directory do not exist; the slot and blockhash come from `upstream.json`.
+pub struct FixtureUpstream {
+    endpoint: String,
+    slot: u64,
+    blockhash: String,
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl FixtureUpstream {
+    /// Read every account dump in `dir`. Unreadable dumps are an error, so a
+    /// broken fixture fails loudly instead of looking like missing accounts.
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read fixture directory {}: {}", dir.display(), e))?;
+
+        let mut upstream = UpstreamDump::default();
+        let mut accounts = HashMap::new();
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+            if path.file_name().and_then(|name| name.to_str()) == Some(FIXTURE_UPSTREAM_FILE) {
+                upstream = serde_json::from_slice(&bytes)
+                    .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+                continue;
+            }
+
+            let dump: AccountDump = serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+            let (pubkey, account) = dump
+                .to_account()
+                .map_err(|e| format!("Invalid account dump {}: {}", path.display(), e))?;
+            accounts.insert(pubkey, account);
+        }
+
+        Ok(Self {
+            endpoint: format!("{}{}", FIXTURE_SCHEME, dir.display()),
+            slot: upstream.slot,
+            blockhash: upstream.blockhash.unwrap_or_else(|| Hash::default().to_string()),
+            accounts,
+        })
+    }
+}
+
+impl Upstream for FixtureUpstream {
+    fn endpoint(&self) -> String {
+        self.endpoint.clone()
+    }
+
+    fn get_slot(&self) -> BoxFuture<'_, Result<u64, String>> {
+        Box::pin(future::ready(Ok(self.slot)))
+    }
+
+    fn get_latest_blockhash(&self) -> BoxFuture<'_, Result<String, String>> {
+        Box::pin(future::ready(Ok(self.blockhash.clone())))
+    }
+
+    fn get_multiple_accounts<'a>(
+        &'a self,
+        pubkeys: &'a [Pubkey],
+        _min_context_slot: Option<u64>,
+    ) -> BoxFuture<'a, Result<AccountsAtSlot, String>> {
+        let accounts = pubkeys.iter().map(|pubkey| self.accounts.get(pubkey).cloned()).collect();
+        Box::pin(future::ready(Ok((self.slot, accounts))))
+    }
+
+    fn get_token_accounts<'a>(
+        &'a self,
+        program_id: &'a Pubkey,
+        owner: &'a Pubkey,
+    ) -> BoxFuture<'a, Result<Vec<(Pubkey, Account)>, String>> {
+        let owner_range = TOKEN_ACCOUNT_OWNER_OFFSET..TOKEN_ACCOUNT_OWNER_OFFSET + 32;
+        let accounts = self
+            .accounts
+            .iter()
+            .filter(|(_, account)| {
+                account.owner == *program_id && account.data.get(owner_range.clone()) == Some(owner.as_ref())
+            })
+            .map(|(pubkey, account)| (*pubkey, account.clone()))
+            .collect();
+        Box::pin(future::ready(Ok(accounts)))
+    }
+}
+
+/// Writes everything fetched from upstream into a fixture directory, one
+/// dump per account, so a live session can be replayed with `file://<dir>`.
+/// Recording failures are logged and never fail the request.
+pub struct FixtureRecorder {
+    dir: PathBuf,
+    /// Last recorded slot and blockhash, rewritten as a whole on every change
+    upstream: Mutex<UpstreamDump>,
+    /// Suffix of temporary files, unique per write
+    next_write: AtomicU64,
+}
+
+impl FixtureRecorder {
+    /// Record into `dir`, creating it if needed. An existing fixture there is added to.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, String> {
+        let dir = dir.into();
+
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create fixture directory {}: {}", dir.display(), e))?;
+
+        let upstream = fs::read(dir.join(FIXTURE_UPSTREAM_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            dir,
+            upstream: Mutex::new(upstream),
+            next_write: AtomicU64::new(0),
+        })
+    }
+
+    pub fn record_accounts<'a>(&self, accounts: impl IntoIterator<Item = (&'a Pubkey, &'a Account)>) {
+        for (pubkey, account) in accounts {
+            let dump = AccountDump::from_account(pubkey, account);
+            if let Err(e) = self.write(&format!("{}.json", pubkey), &dump) {
+                eprintln!("Warning: Failed to record account {}: {}", pubkey, e);
+            }
+        }
+    }
+
+    pub fn record_slot(&self, slot: u64) {
+        self.update_upstream(|upstream| upstream.slot = slot);
+    }
+
+    pub fn record_blockhash(&self, blockhash: &str) {
+        self.update_upstream(|upstream| upstream.blockhash = Some(blockhash.to_string()));
+    }
+
+    fn update_upstream(&self, update: impl FnOnce(&mut UpstreamDump)) {
+        let mut upstream = self.upstream.lock().unwrap_or_else(|e| e.into_inner());
+        update(&mut upstream);
+
+        if let Err(e) = self.write(FIXTURE_UPSTREAM_FILE, &*upstream) {
+            eprintln!("Warning: Failed to record slot and blockhash: {}", e);
+        }
+    }
+
+    /// Write a file in the fixture directory, replacing any previous version atomically
+    fn write(&self, file_name: &str, value: &impl Serialize) -> Result<(), String> {
+        let path = self.dir.join(file_name);
+        let tmp_path = self
+            .dir
+            .join(format!("{}.{}.tmp", file_name, self.next_write.fetch_add(1, Ordering::Relaxed)));
+
+        let bytes = serde_json::to_vec_pretty(value).map_err(|e| format!("Failed to serialize {}: {}", file_name, e))?;
+
+        fs::write(&tmp_path, bytes).map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+impl AccountDump {
+    fn from_account(pubkey: &Pubkey, account: &Account) -> Self {
+        Self {
+            pubkey: pubkey.to_string(),
+            account: DumpedAccount {
+                lamports: account.lamports,
+                data: (BASE64.encode(&account.data), "base64".to_string()),
+                owner: account.owner.to_string(),
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+                space: Some(account.data.len() as u64),
+            },
+        }
+    }
+
+    fn to_account(&self) -> Result<(Pubkey, Account), String> {
+        let pubkey = Pubkey::from_str(&self.pubkey)
+            .map_err(|e| format!("Invalid pubkey {}: {}", self.pubkey, e))?;
+        let owner = Pubkey::from_str(&self.account.owner)
+            .map_err(|e| format!("Invalid owner {}: {}", self.account.owner, e))?;
+
+        let (data, encoding) = &self.account.data;
+        let data = match encoding.as_str() {
+            "base64" => BASE64.decode(data).map_err(|e| format!("Invalid base64 data: {}", e))?,
+            "base58" => bs58::decode(data).into_vec().map_err(|e| format!("Invalid base58 data: {}", e))?,
+            other => return Err(format!("Unsupported data encoding {}", other)),
+        };
+
+        Ok((
+            pubkey,
+            Account {
+                lamports: self.account.lamports,
+                data,
+                owner,
+                executable: self.account.executable,
+                rent_epoch: self.account.rent_epoch,
+            },
+        ))
+    }
+}
@@ -1,148 +1,529 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::models::Fork;
-use crate::services::MainnetClient;
+use solana_account::Account;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_pubkey::Pubkey;
+use solana_transaction::versioned::VersionedTransaction;
+use tokio::sync::RwLock;
 
-/// Maximum fork lifetime in seconds (15 minutes)
-const FORK_LIFETIME_SECONDS: u64 = 15 * 60;
+use crate::config::{Config, ForkLimits, RpcSettings};
+use crate::constants::{DEFAULT_FORK_LIFETIME_SECONDS, DEFAULT_RPC_URL};
+use crate::models::{Fork, ForkSnapshot, ForkState};
+use crate::services::{AccountCache, FixtureRecorder, ForkStore, MainnetClient};
+use crate::utils::get_upgradeable_loader_state;
 
-/// Manages multiple forks for different users
-pub struct ForkManager {
-    forks: HashMap<String, Fork>,
+/// A fork behind its own lock, so requests against different forks run in
+/// parallel. The lock is async, so a request waiting on a busy fork does not
+/// tie up a runtime thread.
+pub type SharedFork = Arc<RwLock<Fork>>;
+
+/// Fetch the accounts among `addresses` that a lazy loading fork is missing,
+/// along with the ProgramData accounts of upgradeable programs among them.
+/// Upstream is queried without holding the fork lock, which is only taken
+/// to find the missing accounts and to install them.
+pub async fn load_accounts(fork: &SharedFork, addresses: &[Pubkey]) -> Result<(), String> {
+    let (upstream, mainnet_slot, missing) = {
+        let fork = fork.read().await;
+        let missing = fork.missing_accounts(addresses);
+        match &fork.upstream {
+            Some(upstream) if !missing.is_empty() => (upstream.clone(), fork.mainnet_slot, missing),
+            _ => return Ok(()),
+        }
+    };
+
+    let accounts = upstream.fetch_accounts_at_slot(&missing, mainnet_slot).await?;
+    let mut fetched: Vec<(Pubkey, Option<Account>)> = missing.into_iter().zip(accounts).collect();
+
+    // Upgradeable programs can only be installed once their ProgramData account exists
+    let programdata_addresses: Vec<Pubkey> = fetched
+        .iter()
+        .filter_map(|(_, account)| match account.as_ref().and_then(get_upgradeable_loader_state) {
+            Some(UpgradeableLoaderState::Program { programdata_address }) => Some(programdata_address),
+            _ => None,
+        })
+        .collect();
+    let programdata_addresses = fork.read().await.missing_accounts(&programdata_addresses);
+    if !programdata_addresses.is_empty() {
+        let programdata = upstream.fetch_accounts_at_slot(&programdata_addresses, mainnet_slot).await?;
+        fetched.extend(programdata_addresses.into_iter().zip(programdata));
+    }
+
+    fork.write().await.install_fetched_accounts(fetched);
+    Ok(())
+}
+
+/// Load every account `transaction` references: its static keys, its
+/// address lookup tables and the addresses they resolve to
+pub async fn load_transaction_accounts(fork: &SharedFork, transaction: &VersionedTransaction) -> Result<(), String> {
+    load_accounts(fork, transaction.message.static_account_keys()).await?;
+
+    let tables: Vec<Pubkey> = transaction
+        .message
+        .address_table_lookups()
+        .map(|lookups| lookups.iter().map(|lookup| lookup.account_key).collect())
+        .unwrap_or_default();
+    if tables.is_empty() {
+        return Ok(());
+    }
+
+    load_accounts(fork, &tables).await?;
+    let (writable, readonly) = fork.read().await.lookup_table_addresses(&transaction.message);
+    let loaded: Vec<Pubkey> = writable.into_iter().chain(readonly).collect();
+    load_accounts(fork, &loaded).await
+}
+
+/// A tracked fork and its snapshots
+#[derive(Clone)]
+struct ForkEntry {
+    fork: SharedFork,
+    /// Snapshots in creation order. Lock the fork first when both are needed.
+    snapshots: Arc<Mutex<Vec<ForkSnapshot>>>,
+    /// Copy of the fork's creation time, so expiry checks never wait on a busy fork
+    created_at: u64,
+}
+
+/// Fork and user bookkeeping, guarded by the manager lock
+#[derive(Default)]
+struct ForkRegistry {
+    forks: HashMap<String, ForkEntry>,
     user_forks: HashMap<String, String>,
 }
 
+/// Manages multiple forks for different users.
+///
+/// The manager lock only guards the fork and user maps and is held just long
+/// enough to look an entry up or change the maps. Work on a fork (executing
+/// transactions, cloning, snapshots, fetching from mainnet) happens under that
+/// fork's own lock, or before the fork is registered at all.
+pub struct ForkManager {
+    registry: Mutex<ForkRegistry>,
+    next_snapshot_id: AtomicU64,
+    /// Suffix that keeps fork ids unique when one user gets several forks within a second
+    next_fork_id: AtomicU64,
+    /// On-disk store forks are persisted to, if enabled
+    store: Option<ForkStore>,
+    /// Seconds a fork lives before cleanup removes it
+    fork_lifetime: u64,
+    /// Forks alive at once; `None` is unlimited
+    max_forks: Option<usize>,
+    /// Limits applied to every fork
+    fork_limits: ForkLimits,
+    /// Upstream imported forks lazy load from, whatever endpoint their state names
+    rpc_url: String,
+    /// Timeouts and retries of the upstream clients of restored and imported forks
+    rpc: RpcSettings,
+    /// Account cache shared by the upstream clients of restored and imported forks
+    account_cache: Option<Arc<AccountCache>>,
+    /// Fixture recorder of the upstream clients of restored and imported forks
+    recorder: Option<Arc<FixtureRecorder>>,
+}
+
 impl ForkManager {
-    /// Create a new fork manager
+    /// Create a new fork manager with the default lifetime and no limits
     pub fn new() -> Self {
         Self {
-            forks: HashMap::new(),
-            user_forks: HashMap::new(),
+            registry: Mutex::new(ForkRegistry::default()),
+            next_snapshot_id: AtomicU64::new(1),
+            next_fork_id: AtomicU64::new(1),
+            store: None,
+            fork_lifetime: DEFAULT_FORK_LIFETIME_SECONDS,
+            max_forks: None,
+            fork_limits: ForkLimits::default(),
+            rpc_url: DEFAULT_RPC_URL.to_string(),
+            rpc: RpcSettings::default(),
+            account_cache: None,
+            recorder: None,
+        }
+    }
+
+    /// Create a fork manager with the lifetime and limits from `config`
+    pub fn with_config(config: &Config) -> Self {
+        Self {
+            fork_lifetime: config.fork_lifetime.as_secs(),
+            max_forks: config.max_forks,
+            fork_limits: config.fork_limits,
+            rpc_url: config.rpc_url.clone(),
+            rpc: config.rpc,
+            ..Self::new()
+        }
+    }
+
+    /// Lock the fork and user maps. They are only ever changed as a whole,
+    /// so a panic elsewhere cannot leave them inconsistent and poisoning is ignored.
+    fn registry(&self) -> MutexGuard<'_, ForkRegistry> {
+        self.registry.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The user's fork, if it has one that has not expired
+    fn live_user_fork(&self, registry: &ForkRegistry, user_id: &str) -> Option<String> {
+        let fork_id = registry.user_forks.get(user_id)?;
+        let entry = registry.forks.get(fork_id)?;
+        (!self.is_expired(entry)).then(|| fork_id.clone())
+    }
+
+    /// Fail if another fork would exceed `max_forks`, after dropping expired ones
+    fn ensure_fork_capacity(&self, registry: &mut ForkRegistry) -> Result<(), String> {
+        let Some(max_forks) = self.max_forks else {
+            return Ok(());
+        };
+
+        if registry.forks.len() >= max_forks {
+            self.remove_expired(registry);
         }
+        if registry.forks.len() >= max_forks {
+            return Err(format!("Fork limit of {} reached", max_forks));
+        }
+
+        Ok(())
+    }
+
+    /// Check capacity before building a fork, so a full server fails fast
+    fn check_fork_capacity(&self) -> Result<(), String> {
+        self.ensure_fork_capacity(&mut self.registry())
+    }
+
+    /// Track a new fork under `user_id`, applying the per-fork limits.
+    /// Capacity is checked again since other forks may have been created
+    /// while this one was being built.
+    fn insert_fork(&self, mut fork: Fork, user_id: Option<String>) -> Result<String, String> {
+        fork.max_transactions = self.fork_limits.max_transactions;
+        let fork_id = fork.id.clone();
+        let created_at = fork.created_at;
+
+        let mut registry = self.registry();
+        if registry.forks.contains_key(&fork_id) {
+            return Err(format!("Fork already exists: {}", fork_id));
+        }
+        self.ensure_fork_capacity(&mut registry)?;
+
+        if let Some(user_id) = user_id {
+            registry.user_forks.insert(user_id, fork_id.clone());
+        }
+        registry.forks.insert(fork_id.clone(), ForkEntry {
+            fork: Arc::new(RwLock::new(fork)),
+            snapshots: Arc::new(Mutex::new(Vec::new())),
+            created_at,
+        });
+
+        Ok(fork_id)
+    }
+
+    /// Track a new fork as the user's fork, unless a concurrent request
+    /// already gave the user one, in which case that fork wins
+    fn insert_user_fork(&self, fork: Fork, user_id: String) -> Result<String, String> {
+        if let Some(fork_id) = self.live_user_fork(&self.registry(), &user_id) {
+            return Ok(fork_id);
+        }
+
+        self.insert_fork(fork, Some(user_id))
     }
 
-    /// Create a new fork for a user, synced with mainnet
-    pub fn create_fork_with_mainnet_sync(
-        &mut self,
+    /// Create a new fork for a user, synced with mainnet.
+    /// With `lazy_load`, accounts missing from the fork are fetched from
+    /// the same endpoint on first access.
+    ///
+    /// Mainnet is queried without holding the manager lock.
+    pub async fn create_fork_with_mainnet_sync(
+        &self,
         user_id: String,
         mainnet_client: &MainnetClient,
+        lazy_load: bool,
     ) -> Result<String, String> {
         // Check if user already has a fork
-        if let Some(fork_id) = self.user_forks.get(&user_id) {
-            if let Some(fork) = self.forks.get(fork_id) {
-                if !self.is_fork_expired(fork) {
-                    return Ok(fork_id.clone());
-                }
-            }
+        if let Some(fork_id) = self.live_user_fork(&self.registry(), &user_id) {
+            return Ok(fork_id);
         }
 
+        self.check_fork_capacity()?;
+
         // Fetch mainnet state
-        let mainnet_slot = mainnet_client.get_slot()?;
-        let mainnet_blockhash = mainnet_client.get_latest_blockhash()?;
+        let mainnet_slot = mainnet_client.get_slot().await?;
+        let mainnet_blockhash = mainnet_client.get_latest_blockhash().await?;
 
         // Create new fork with mainnet sync
-        let fork_id = format!("fork-{}-{}", user_id, Self::current_timestamp());
-        let fork = Fork::new_with_mainnet_sync(
-            fork_id.clone(),
+        let fork_id = self.new_fork_id(&user_id);
+        let mut fork = Fork::new_with_mainnet_sync(
+            fork_id,
             mainnet_slot,
             mainnet_blockhash,
         );
+        fork.sync_sysvars(mainnet_client).await?;
 
-        self.forks.insert(fork_id.clone(), fork);
-        self.user_forks.insert(user_id, fork_id.clone());
+        if lazy_load {
+            fork.enable_lazy_loading(mainnet_client.clone());
+        }
 
-        Ok(fork_id)
+        self.insert_user_fork(fork, user_id)
     }
 
-    pub fn create_fork(&mut self, user_id: String) -> String {
+    pub fn create_fork(&self, user_id: String) -> Result<String, String> {
         // Check if user already has a fork
-        if let Some(fork_id) = self.user_forks.get(&user_id) {
-            // Check if fork is still valid
-            if let Some(fork) = self.forks.get(fork_id) {
-                if !self.is_fork_expired(fork) {
-                    return fork_id.clone();
-                }
-            }
+        // and that the fork is still valid
+        if let Some(fork_id) = self.live_user_fork(&self.registry(), &user_id) {
+            return Ok(fork_id);
         }
 
-        // Create new fork
-        let fork_id = format!("fork-{}-{}", user_id, Self::current_timestamp());
-        let fork = Fork::new(fork_id.clone());
+        self.check_fork_capacity()?;
 
-        self.forks.insert(fork_id.clone(), fork);
-        self.user_forks.insert(user_id, fork_id.clone());
+        // Create new fork
+        let fork_id = self.new_fork_id(&user_id);
+        let fork = Fork::new(fork_id);
 
-        fork_id
+        self.insert_user_fork(fork, user_id)
     }
 
-    /// Get a fork by ID
-    pub fn get_fork(&self, fork_id: &str) -> Option<&Fork> {
-        self.forks.get(fork_id)
+    /// Clone an existing fork into a new independent fork owned by `user_id`
+    pub async fn clone_fork(&self, source_fork_id: &str, user_id: String) -> Result<String, String> {
+        self.check_fork_capacity()?;
+
+        let source = self.get_fork(source_fork_id)
+            .ok_or_else(|| format!("Fork not found: {}", source_fork_id))?;
+
+        let fork_id = self.new_fork_id(&user_id);
+        let fork = source.read().await.clone_as(fork_id);
+
+        self.insert_fork(fork, Some(user_id))
     }
 
-    /// Get a mutable fork by ID
-    pub fn get_fork_mut(&mut self, fork_id: &str) -> Option<&mut Fork> {
-        self.forks.get_mut(fork_id)
+    /// Get a fork by ID. Lock the returned fork to use it; the manager is
+    /// not locked while the caller holds it.
+    pub fn get_fork(&self, fork_id: &str) -> Option<SharedFork> {
+        self.registry().forks.get(fork_id).map(|entry| entry.fork.clone())
     }
 
     /// Get fork ID for a user
-    pub fn get_user_fork_id(&self, user_id: &str) -> Option<&String> {
-        self.user_forks.get(user_id)
+    pub fn get_user_fork_id(&self, user_id: &str) -> Option<String> {
+        self.registry().user_forks.get(user_id).cloned()
     }
 
     /// Check if a fork has expired
-    fn is_fork_expired(&self, fork: &Fork) -> bool {
+    fn is_expired(&self, entry: &ForkEntry) -> bool {
         let current_time = Self::current_timestamp();
-        current_time - fork.created_at > FORK_LIFETIME_SECONDS
+        current_time.saturating_sub(entry.created_at) > self.fork_lifetime
     }
 
-    /// Clean up expired forks
-    pub fn cleanup_expired_forks(&mut self) -> usize {
-        let mut expired_fork_ids = Vec::new();
-
-        // Find expired forks
-        for (fork_id, fork) in &self.forks {
-            if self.is_fork_expired(fork) {
-                expired_fork_ids.push(fork_id.clone());
-            }
-        }
-
-        let count = expired_fork_ids.len();
+    /// Drop expired forks from the maps, returning how many were removed
+    fn remove_expired(&self, registry: &mut ForkRegistry) -> usize {
+        let expired_fork_ids: Vec<String> = registry.forks
+            .iter()
+            .filter(|(_, entry)| self.is_expired(entry))
+            .map(|(fork_id, _)| fork_id.clone())
+            .collect();
 
-        // Remove expired forks
-        for fork_id in expired_fork_ids {
-            self.forks.remove(&fork_id);
+        // Remove expired forks, their snapshots go with them
+        for fork_id in &expired_fork_ids {
+            registry.forks.remove(fork_id);
 
             // Remove from user mapping
-            self.user_forks.retain(|_, fid| fid != &fork_id);
+            registry.user_forks.retain(|_, fid| fid != fork_id);
         }
 
-        count
+        expired_fork_ids.len()
+    }
+
+    /// Clean up expired forks
+    pub fn cleanup_expired_forks(&self) -> usize {
+        self.remove_expired(&mut self.registry())
     }
 
     /// Get total number of active forks
     pub fn active_fork_count(&self) -> usize {
-        self.forks.len()
+        self.registry().forks.len()
     }
 
     /// Get all fork IDs
     pub fn get_all_fork_ids(&self) -> Vec<String> {
-        self.forks.keys().cloned().collect()
+        self.registry().forks.keys().cloned().collect()
     }
 
     /// Delete a specific fork
-    pub fn delete_fork(&mut self, fork_id: &str) -> bool {
-        if self.forks.remove(fork_id).is_some() {
+    pub fn delete_fork(&self, fork_id: &str) -> bool {
+        let mut registry = self.registry();
+        if registry.forks.remove(fork_id).is_some() {
             // Remove from user mapping
-            self.user_forks.retain(|_, fid| fid != fork_id);
+            registry.user_forks.retain(|_, fid| fid != fork_id);
             true
         } else {
             false
         }
     }
 
+    fn get_entry(&self, fork_id: &str) -> Result<ForkEntry, String> {
+        self.registry()
+            .forks
+            .get(fork_id)
+            .cloned()
+            .ok_or_else(|| format!("Fork not found: {}", fork_id))
+    }
+
+    /// Snapshot a fork's current state, returning the snapshot ID
+    pub async fn create_snapshot(&self, fork_id: &str) -> Result<String, String> {
+        let entry = self.get_entry(fork_id)?;
+
+        let check_limit = |snapshots: &[ForkSnapshot]| match self.fork_limits.max_snapshots {
+            Some(max_snapshots) if snapshots.len() >= max_snapshots => {
+                Err(format!("Snapshot limit of {} reached for fork {}", max_snapshots, fork_id))
+            }
+            _ => Ok(()),
+        };
+
+        check_limit(&lock_snapshots(&entry.snapshots))?;
+
+        let snapshot_id = format!("snapshot-{}", self.next_snapshot_id.fetch_add(1, Ordering::Relaxed));
+
+        let snapshot = entry.fork.read().await.snapshot(snapshot_id.clone());
+
+        let mut snapshots = lock_snapshots(&entry.snapshots);
+        check_limit(&snapshots)?;
+        snapshots.push(snapshot);
+
+        Ok(snapshot_id)
+    }
+
+    /// Revert a fork to a snapshot. Snapshots taken after it are discarded,
+    /// while the snapshot itself is kept so the fork can be reverted to it again.
+    pub async fn revert_to_snapshot(&self, fork_id: &str, snapshot_id: &str) -> Result<u64, String> {
+        let entry = self.get_entry(fork_id)?;
+
+        let mut fork = entry.fork.write().await;
+        let mut snapshots = lock_snapshots(&entry.snapshots);
+
+        let index = snapshots.iter()
+            .position(|snapshot| snapshot.id == snapshot_id)
+            .ok_or_else(|| format!("Snapshot not found: {}", snapshot_id))?;
+
+        fork.revert(&snapshots[index]);
+        snapshots.truncate(index + 1);
+
+        Ok(fork.slot)
+    }
+
+    /// Persist forks to `store` and restore them on startup
+    pub fn enable_persistence(&mut self, store: ForkStore) {
+        self.store = Some(store);
+    }
+
+    /// Share `cache` with the upstream clients of restored and imported forks
+    pub fn enable_account_cache(&mut self, cache: Arc<AccountCache>) {
+        self.account_cache = Some(cache);
+    }
+
+    /// Record what the upstream clients of restored and imported forks fetch with `recorder`
+    pub fn enable_recording(&mut self, recorder: Arc<FixtureRecorder>) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Restore all forks found in the persistence store
+    pub fn restore_forks(&self) -> Result<usize, String> {
+        let Some(store) = self.store.as_ref() else {
+            return Ok(0);
+        };
+
+        let mut restored = 0;
+
+        for state in store.load_all()? {
+            // Our own data directory, so the stored upstream is trusted
+            let fork = state
+                .rpc_endpoint
+                .as_deref()
+                .map(|endpoint| self.open_upstream(endpoint))
+                .transpose()
+                .and_then(|upstream| Fork::from_state(&state, upstream));
+            match fork.and_then(|fork| self.insert_fork(fork, state.user_id)) {
+                Ok(_) => restored += 1,
+                Err(e) => eprintln!("Warning: Failed to restore fork {}: {}", state.fork_id, e),
+            }
+        }
+
+        Ok(restored)
+    }
+
+    /// Write all forks to the persistence store and remove files of forks that no longer exist.
+    /// Each fork is serialized under its own lock, one at a time.
+    pub async fn persist_forks(&self) -> Result<usize, String> {
+        let Some(store) = self.store.as_ref() else {
+            return Ok(0);
+        };
+
+        let fork_ids = self.get_all_fork_ids();
+
+        for fork_id in &fork_ids {
+            match self.export_fork(fork_id).await {
+                Ok(state) => store.save(&state)?,
+                // Deleted since the IDs were listed
+                Err(_) if self.get_fork(fork_id).is_none() => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        store.retain(&self.get_all_fork_ids())?;
+
+        Ok(fork_ids.len())
+    }
+
+    /// Serialize a fork so it can be shared or checked into test fixtures
+    pub async fn export_fork(&self, fork_id: &str) -> Result<ForkState, String> {
+        let (fork, user_id) = {
+            let registry = self.registry();
+            let entry = registry.forks.get(fork_id)
+                .ok_or_else(|| format!("Fork not found: {}", fork_id))?;
+
+            let user_id = registry.user_forks
+                .iter()
+                .find(|(_, fid)| fid.as_str() == fork_id)
+                .map(|(uid, _)| uid.clone());
+
+            (entry.fork.clone(), user_id)
+        };
+
+        let fork = fork.read().await;
+        Ok(fork.to_state(user_id))
+    }
+
+    /// Import a serialized fork as a new fork owned by `user_id`. The state
+    /// comes from a client, so a lazy loading fork loads from the configured
+    /// default upstream rather than the endpoint the state names.
+    pub fn import_fork(&self, state: &ForkState, user_id: String) -> Result<String, String> {
+        self.check_fork_capacity()?;
+
+        let upstream = state
+            .rpc_endpoint
+            .as_ref()
+            .map(|_| self.open_upstream(&self.rpc_url))
+            .transpose()?;
+        let mut fork = Fork::from_state(state, upstream)?;
+        fork.id = self.new_fork_id(&user_id);
+        fork.created_at = Self::current_timestamp();
+
+        self.insert_fork(fork, Some(user_id))
+    }
+
+    /// Open the upstream client of a deserialized fork with the configured
+    /// timeouts, retries, account cache and recorder
+    fn open_upstream(&self, endpoint: &str) -> Result<MainnetClient, String> {
+        let mut client = MainnetClient::open(endpoint, self.rpc)?;
+        if let Some(cache) = &self.account_cache {
+            client = client.with_cache(cache.clone());
+        }
+        if let Some(recorder) = &self.recorder {
+            client = client.with_recorder(recorder.clone());
+        }
+        Ok(client)
+    }
+
+    /// A fresh fork id for `user_id`
+    fn new_fork_id(&self, user_id: &str) -> String {
+        format!(
+            "fork-{}-{}-{}",
+            user_id,
+            Self::current_timestamp(),
+            self.next_fork_id.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
     /// Get current timestamp in seconds
     fn current_timestamp() -> u64 {
         SystemTime::now()
@@ -152,10 +533,22 @@ impl ForkManager {
     }
 }
 
-/// Thread-safe fork manager
-pub type SharedForkManager = Arc<Mutex<ForkManager>>;
+impl Default for ForkManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-/// Create a new shared fork manager
-pub fn create_shared_fork_manager() -> SharedForkManager {
-    Arc::new(Mutex::new(ForkManager::new()))
-}
\ No newline at end of file
+/// Lock a fork's snapshots. A snapshot is pushed or the list truncated in one
+/// step, so poisoning is ignored.
+fn lock_snapshots(snapshots: &Mutex<Vec<ForkSnapshot>>) -> MutexGuard<'_, Vec<ForkSnapshot>> {
+    snapshots.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Fork manager shared between handlers. It locks internally, so no outer lock is needed.
+pub type SharedForkManager = Arc<ForkManager>;
+
+/// Create a new shared fork manager configured by `config`
+pub fn create_shared_fork_manager(config: &Config) -> SharedForkManager {
+    Arc::new(ForkManager::with_config(config))
+}
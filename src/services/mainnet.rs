@@ -1,102 +1,218 @@
-use solana_client::rpc_client::RpcClient;
+use futures::future::try_join_all;
 use solana_account::Account;
 use solana_pubkey::Pubkey;
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 
+use crate::config::RpcSettings;
+use crate::constants::DEFAULT_RPC_URL;
+use crate::services::{AccountCache, FixtureRecorder, FixtureUpstream, RpcUpstream, Upstream, FIXTURE_SCHEME};
+
+/// Maximum number of addresses accepted by a single `getMultipleAccounts` call
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Client for the upstream a fork is created from: an RPC node, or a fixture
+/// directory for `file://` endpoints. Account fetches are batched and go
+/// through the shared account cache when one is set. Clones share the
+/// underlying upstream, cache and recorder.
+#[derive(Clone)]
 pub struct MainnetClient {
-    rpc_client: RpcClient,
+    upstream: Arc<dyn Upstream>,
+    /// Cache shared with the clients of other forks, if any
+    cache: Option<Arc<AccountCache>>,
+    /// Fixture directory everything fetched is recorded into, if any
+    recorder: Option<Arc<FixtureRecorder>>,
 }
 
 impl MainnetClient {
     /// Create a new mainnet client with default RPC endpoint
     pub fn new() -> Self {
-        Self::with_endpoint("https://api.mainnet-beta.solana.com")
+        Self::with_endpoint(DEFAULT_RPC_URL)
     }
 
     /// Create a new mainnet client with custom RPC endpoint
     pub fn with_endpoint(endpoint: &str) -> Self {
+        Self::with_settings(endpoint, RpcSettings::default())
+    }
+
+    /// Create a mainnet client for the RPC `endpoint` with custom timeouts and retries
+    pub fn with_settings(endpoint: &str, settings: RpcSettings) -> Self {
+        Self::with_upstream(Arc::new(RpcUpstream::new(endpoint, settings)))
+    }
+
+    /// Open `endpoint`: a fixture directory for `file://<path>`, an RPC node otherwise
+    pub fn open(endpoint: &str, settings: RpcSettings) -> Result<Self, String> {
+        match endpoint.strip_prefix(FIXTURE_SCHEME) {
+            Some(dir) => Ok(Self::with_upstream(Arc::new(FixtureUpstream::open(Path::new(dir))?))),
+            None => Ok(Self::with_settings(endpoint, settings)),
+        }
+    }
+
+    /// Create a mainnet client for any upstream implementation
+    pub fn with_upstream(upstream: Arc<dyn Upstream>) -> Self {
         Self {
-            rpc_client: RpcClient::new(endpoint.to_string()),
+            upstream,
+            cache: None,
+            recorder: None,
         }
     }
 
+    /// Serve account fetches from `cache` where possible and add fetched accounts to it
+    pub fn with_cache(mut self, cache: Arc<AccountCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Record every account, slot and blockhash this client returns into a fixture directory
+    pub fn with_recorder(mut self, recorder: Arc<FixtureRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Get the endpoint this client talks to
+    pub fn endpoint(&self) -> String {
+        self.upstream.endpoint()
+    }
+
     /// Fetch an account from mainnet
-    pub fn fetch_account(&self, address: &str) -> Result<Account, String> {
+    pub async fn fetch_account(&self, address: &str) -> Result<Account, String> {
         let pubkey = Pubkey::from_str(address)
             .map_err(|e| format!("Invalid pubkey: {}", e))?;
 
-        self.rpc_client
-            .get_account(&pubkey)
-            .map_err(|e| format!("Failed to fetch account: {}", e))
+        self.get_multiple_accounts(&[pubkey], None)
+            .await?
+            .pop()
+            .flatten()
+            .ok_or_else(|| format!("Failed to fetch account: AccountNotFound: pubkey={}", pubkey))
+    }
+
+    /// Fetch multiple accounts from mainnet, one `getMultipleAccounts` call per
+    /// 100 addresses. Accounts that do not exist on mainnet are returned as `None`.
+    pub async fn fetch_accounts(&self, addresses: &[String]) -> Result<Vec<(String, Option<Account>)>, String> {
+        let pubkeys = addresses
+            .iter()
+            .map(|address| {
+                Pubkey::from_str(address).map_err(|e| format!("Invalid pubkey {}: {}", address, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let accounts = self.get_multiple_accounts(&pubkeys, None).await?;
+
+        Ok(addresses.iter().cloned().zip(accounts).collect())
+    }
+
+    /// Fetch multiple accounts from a node that has reached at least `min_context_slot`.
+    /// Accounts that do not exist on mainnet are returned as `None`.
+    pub async fn fetch_accounts_at_slot(
+        &self,
+        pubkeys: &[Pubkey],
+        min_context_slot: u64,
+    ) -> Result<Vec<Option<Account>>, String> {
+        self.get_multiple_accounts(pubkeys, Some(min_context_slot)).await
     }
 
-    /// Fetch multiple accounts from mainnet
-    pub fn fetch_accounts(&self, addresses: &[String]) -> Result<Vec<(String, Account)>, String> {
-        let mut results = Vec::new();
-        
-        for address in addresses {
-            match self.fetch_account(address) {
-                Ok(account) => results.push((address.clone(), account)),
-                Err(e) => return Err(format!("Failed to fetch {}: {}", address, e)),
+    /// `getMultipleAccounts` over any number of addresses: accounts in the
+    /// cache are served from it, the rest are requested concurrently in
+    /// chunks of 100 and cached. Results are returned in address order and
+    /// recorded if recording is enabled.
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> Result<Vec<Option<Account>>, String> {
+        let endpoint = self.endpoint();
+        let mut accounts = match &self.cache {
+            Some(cache) => cache.get_many(&endpoint, pubkeys, min_context_slot),
+            None => vec![None; pubkeys.len()],
+        };
+
+        let missing: Vec<Pubkey> = pubkeys
+            .iter()
+            .zip(&accounts)
+            .filter(|(_, account)| account.is_none())
+            .map(|(pubkey, _)| *pubkey)
+            .collect();
+
+        let chunks = try_join_all(
+            missing
+                .chunks(MAX_MULTIPLE_ACCOUNTS)
+                .map(|chunk| self.upstream.get_multiple_accounts(chunk, min_context_slot)),
+        )
+        .await?;
+
+        let mut fetched = Vec::with_capacity(missing.len());
+        for (chunk, (slot, accounts)) in missing.chunks(MAX_MULTIPLE_ACCOUNTS).zip(chunks) {
+            let chunk_accounts: Vec<_> = chunk.iter().copied().zip(accounts).collect();
+            if let Some(cache) = &self.cache {
+                cache.insert_many(&endpoint, slot, &chunk_accounts);
             }
+            fetched.extend(chunk_accounts.into_iter().map(|(_, account)| account));
         }
 
-        Ok(results)
+        // Fill the misses in address order
+        let mut fetched = fetched.into_iter();
+        for account in accounts.iter_mut().filter(|account| account.is_none()) {
+            *account = fetched.next();
+        }
+
+        let accounts: Vec<Option<Account>> = accounts.into_iter().map(Option::flatten).collect();
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_accounts(
+                pubkeys
+                    .iter()
+                    .zip(&accounts)
+                    .filter_map(|(pubkey, account)| Some((pubkey, account.as_ref()?))),
+            );
+        }
+
+        Ok(accounts)
     }
 
-    /// Fetch all token accounts owned by an address
-    pub fn fetch_token_accounts(&self, owner: &str) -> Result<Vec<(String, Account)>, String> {
+    /// Fetch all SPL Token and Token-2022 accounts owned by an address
+    pub async fn fetch_token_accounts(&self, owner: &str) -> Result<Vec<(String, Account)>, String> {
         let owner_pubkey = Pubkey::from_str(owner)
             .map_err(|e| format!("Invalid owner pubkey: {}", e))?;
 
-        // Get the SPL Token program ID
-        let token_program_id = spl_token::id();
+        // Fetch the owner's accounts under either token program
+        let token_program_ids = [spl_token::id(), spl_token_2022_interface::id()];
+        let owned_accounts: Vec<(Pubkey, Account)> = try_join_all(
+            token_program_ids
+                .iter()
+                .map(|token_program_id| self.upstream.get_token_accounts(token_program_id, &owner_pubkey)),
+        )
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
 
-        // Fetch all accounts owned by the token program
-        let accounts = self.rpc_client
-            .get_program_accounts(&token_program_id)
-            .map_err(|e| format!("Failed to fetch token accounts: {}", e))?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record_accounts(owned_accounts.iter().map(|(pubkey, account)| (pubkey, account)));
+        }
 
-        // Filter accounts where the owner field matches our owner pubkey
-        let owned_accounts: Vec<(String, Account)> = accounts
+        Ok(owned_accounts
             .into_iter()
-            .filter_map(|(pubkey, account)| {
-                // Token account data structure:
-                // - First 32 bytes: mint pubkey
-                // - Next 32 bytes: owner pubkey (bytes 32-64)
-                // - Remaining: amount, delegate, state, etc.
-                
-                if account.data.len() >= 64 {
-                    let owner_bytes = &account.data[32..64];
-                    
-                    // Check if this token account is owned by the specified address
-                    if owner_bytes == owner_pubkey.to_bytes() {
-                        Some((pubkey.to_string(), account))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        Ok(owned_accounts)
+            .map(|(pubkey, account)| (pubkey.to_string(), account))
+            .collect())
     }
 
     /// Get the latest blockhash from mainnet
-    pub fn get_latest_blockhash(&self) -> Result<String, String> {
-        self.rpc_client
-            .get_latest_blockhash()
-            .map(|hash| hash.to_string())
-            .map_err(|e| format!("Failed to get blockhash: {}", e))
+    pub async fn get_latest_blockhash(&self) -> Result<String, String> {
+        let blockhash = self.upstream.get_latest_blockhash().await?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record_blockhash(&blockhash);
+        }
+        Ok(blockhash)
     }
 
     /// Get slot information
-    pub fn get_slot(&self) -> Result<u64, String> {
-        self.rpc_client
-            .get_slot()
-            .map_err(|e| format!("Failed to get slot: {}", e))
+    pub async fn get_slot(&self) -> Result<u64, String> {
+        let slot = self.upstream.get_slot().await?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record_slot(slot);
+        }
+        Ok(slot)
     }
 }
 
@@ -104,4 +220,4 @@ impl Default for MainnetClient {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,15 @@
+pub mod account_cache;
+pub mod fixture;
 pub mod mainnet;
 pub mod fork_manager;
+pub mod persistence;
+pub mod upstream;
 
+pub use account_cache::{AccountCache, AccountCacheStats};
+pub use fixture::{check_fixture_endpoint, FixtureRecorder, FixtureUpstream, FIXTURE_SCHEME};
 pub use mainnet::*;
-pub use fork_manager::{create_shared_fork_manager, SharedForkManager};
+pub use fork_manager::{
+    create_shared_fork_manager, load_accounts, load_transaction_accounts, ForkManager, SharedFork, SharedForkManager,
+};
+pub use persistence::ForkStore;
+pub use upstream::{AccountsAtSlot, RpcUpstream, Upstream};
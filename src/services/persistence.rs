@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::models::ForkState;
+
+/// Stores fork state files (one JSON file per fork) in a data directory
+pub struct ForkStore {
+    data_dir: PathBuf,
+}
+
+impl ForkStore {
+    /// Create a store backed by `data_dir`, creating the directory if needed
+    pub fn new(data_dir: impl Into<PathBuf>) -> Result<Self, String> {
+        let data_dir = data_dir.into();
+
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("Failed to create data directory {}: {}", data_dir.display(), e))?;
+
+        Ok(Self { data_dir })
+    }
+
+    /// Write a fork state file, replacing any previous version atomically
+    pub fn save(&self, state: &ForkState) -> Result<(), String> {
+        let path = self.path_for(&state.fork_id);
+        let tmp_path = path.with_extension("json.tmp");
+
+        let bytes = serde_json::to_vec(state)
+            .map_err(|e| format!("Failed to serialize fork {}: {}", state.fork_id, e))?;
+
+        fs::write(&tmp_path, bytes)
+            .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, &path)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Load every fork state file in the data directory.
+    /// Unreadable files are skipped with a warning.
+    pub fn load_all(&self) -> Result<Vec<ForkState>, String> {
+        let entries = fs::read_dir(&self.data_dir)
+            .map_err(|e| format!("Failed to read {}: {}", self.data_dir.display(), e))?;
+
+        let mut states = Vec::new();
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let state = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| serde_json::from_slice::<ForkState>(&bytes).map_err(|e| e.to_string()));
+
+            match state {
+                Ok(state) => states.push(state),
+                Err(e) => eprintln!("Warning: Failed to load fork state {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(states)
+    }
+
+    /// Delete the state files of all forks not in `fork_ids`
+    pub fn retain(&self, fork_ids: &[String]) -> Result<(), String> {
+        let keep: HashSet<PathBuf> = fork_ids.iter().map(|id| self.path_for(id)).collect();
+
+        let entries = fs::read_dir(&self.data_dir)
+            .map_err(|e| format!("Failed to read {}: {}", self.data_dir.display(), e))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("json") && !keep.contains(&path) {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// File path for a fork. Bytes other than ASCII letters, digits and `-`
+    /// are written as `_` and two hex digits (`_` itself included), so
+    /// distinct fork ids never share a file.
+    fn path_for(&self, fork_id: &str) -> PathBuf {
+        let mut file_name = String::with_capacity(fork_id.len());
+        for byte in fork_id.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'-' {
+                file_name.push(byte as char);
+            } else {
+                file_name.push_str(&format!("_{:02x}", byte));
+            }
+        }
+
+        self.data_dir.join(format!("{}.json", file_name))
+    }
+}
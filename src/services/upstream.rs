@@ -0,0 +1,179 @@
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use solana_account::Account;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_pubkey::Pubkey;
+use spl_token::solana_program::program_pack::Pack;
+use std::future::Future;
+
+use crate::config::RpcSettings;
+
+/// The slot accounts were read at, and the accounts in address order
+/// (`None` for one that does not exist)
+pub type AccountsAtSlot = (u64, Vec<Option<Account>>);
+
+/// Source of mainnet state for `MainnetClient`: a live RPC node, or a
+/// directory of account dumps for tests that must not touch the network
+pub trait Upstream: Send + Sync {
+    /// Endpoint the upstream was opened from
+    fn endpoint(&self) -> String;
+
+    fn get_slot(&self) -> BoxFuture<'_, Result<u64, String>>;
+
+    fn get_latest_blockhash(&self) -> BoxFuture<'_, Result<String, String>>;
+
+    /// Fetch up to 100 accounts in one request
+    fn get_multiple_accounts<'a>(
+        &'a self,
+        pubkeys: &'a [Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> BoxFuture<'a, Result<AccountsAtSlot, String>>;
+
+    /// Fetch the token accounts of `owner` held under the token program `program_id`
+    fn get_token_accounts<'a>(
+        &'a self,
+        program_id: &'a Pubkey,
+        owner: &'a Pubkey,
+    ) -> BoxFuture<'a, Result<Vec<(Pubkey, Account)>, String>>;
+}
+
+/// Offset of the owner in a token account, after the mint
+pub const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+
+/// Upstream backed by an RPC node. Requests are async, time out and are
+/// retried with exponential backoff according to its `RpcSettings`.
+pub struct RpcUpstream {
+    rpc_client: RpcClient,
+    settings: RpcSettings,
+}
+
+impl RpcUpstream {
+    pub fn new(endpoint: &str, settings: RpcSettings) -> Self {
+        Self {
+            rpc_client: RpcClient::new_with_timeout(endpoint.to_string(), settings.timeout),
+            settings,
+        }
+    }
+
+    /// Run `request`, retrying transient failures with exponential backoff
+    async fn with_retries<T, F, Fut>(&self, request: F) -> Result<T, ClientError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let mut retries = 0;
+
+        loop {
+            match request().await {
+                Err(e) if retries < self.settings.max_retries && is_transient(&e) => {
+                    let backoff = self.settings.retry_backoff.saturating_mul(1 << retries.min(16));
+                    tokio::time::sleep(backoff).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Upstream for RpcUpstream {
+    fn endpoint(&self) -> String {
+        self.rpc_client.url()
+    }
+
+    fn get_slot(&self) -> BoxFuture<'_, Result<u64, String>> {
+        async move {
+            self.with_retries(|| self.rpc_client.get_slot())
+                .await
+                .map_err(|e| format!("Failed to get slot: {}", e))
+        }
+        .boxed()
+    }
+
+    fn get_latest_blockhash(&self) -> BoxFuture<'_, Result<String, String>> {
+        async move {
+            self.with_retries(|| self.rpc_client.get_latest_blockhash())
+                .await
+                .map(|hash| hash.to_string())
+                .map_err(|e| format!("Failed to get blockhash: {}", e))
+        }
+        .boxed()
+    }
+
+    fn get_multiple_accounts<'a>(
+        &'a self,
+        pubkeys: &'a [Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> BoxFuture<'a, Result<AccountsAtSlot, String>> {
+        async move {
+            let config = RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                min_context_slot,
+                ..RpcAccountInfoConfig::default()
+            };
+
+            self.with_retries(|| self.rpc_client.get_multiple_accounts_with_config(pubkeys, config.clone()))
+                .await
+                .map(|response| (response.context.slot, response.value))
+                .map_err(|e| format!("Failed to fetch accounts: {}", e))
+        }
+        .boxed()
+    }
+
+    fn get_token_accounts<'a>(
+        &'a self,
+        program_id: &'a Pubkey,
+        owner: &'a Pubkey,
+    ) -> BoxFuture<'a, Result<Vec<(Pubkey, Account)>, String>> {
+        async move {
+            // Let the node filter by owner; an unfiltered scan of a token
+            // program is rejected or times out on mainnet
+            let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                TOKEN_ACCOUNT_OWNER_OFFSET,
+                owner.as_ref(),
+            ))];
+            if *program_id == spl_token::id() {
+                filters.push(RpcFilterType::DataSize(spl_token::state::Account::LEN as u64));
+            } else {
+                // Token-2022 accounts vary in size with their extensions
+                filters.push(RpcFilterType::TokenAccountState);
+            }
+
+            let config = RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            };
+
+            self.with_retries(|| self.rpc_client.get_program_accounts_with_config(program_id, config.clone()))
+                .await
+                .map_err(|e| format!("Failed to fetch token accounts: {}", e))
+        }
+        .boxed()
+    }
+}
+
+/// Whether a failed request may succeed if sent again: rate limiting, server
+/// errors, timeouts and connection failures
+fn is_transient(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Reqwest(error) => {
+            error.is_timeout()
+                || error.is_connect()
+                || error
+                    .status()
+                    .is_some_and(|status| status.as_u16() == 429 || status.is_server_error())
+        }
+        ClientErrorKind::Io(_) => true,
+        _ => false,
+    }
+}
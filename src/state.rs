@@ -1,13 +1,69 @@
-use std::sync::{Arc, Mutex};
-use crate::services::fork_manager::ForkManager;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use crate::config::Config;
+use crate::services::fork_manager::SharedForkManager;
+use crate::services::{check_fixture_endpoint, AccountCache, FixtureRecorder, MainnetClient};
 
 #[derive(Clone)] 
 pub struct AppState {
-    pub fork_manager: Arc<Mutex<ForkManager>>,
+    pub fork_manager: SharedForkManager,
+    pub config: Arc<Config>,
+    /// Upstream accounts shared by every fork's mainnet client
+    pub account_cache: Arc<AccountCache>,
+    /// Fixture directory upstream fetches are recorded into, if recording is enabled
+    pub recorder: Option<Arc<FixtureRecorder>>,
+    /// Base URL clients use to reach this server, derived from the bind address
+    pub public_url: String,
 }
 
 impl AppState {
-    pub fn new(fork_manager: Arc<Mutex<ForkManager>>) -> Self {
-        Self { fork_manager }
+    pub fn new(
+        fork_manager: SharedForkManager,
+        account_cache: Arc<AccountCache>,
+        recorder: Option<Arc<FixtureRecorder>>,
+        config: Arc<Config>,
+    ) -> Self {
+        Self {
+            fork_manager,
+            account_cache,
+            recorder,
+            public_url: public_url_for(&config.server_addr),
+            config,
+        }
     }
-}
\ No newline at end of file
+
+    /// Client for `endpoint`, or for the configured default endpoint, backed
+    /// by the shared account cache and recording if enabled. A `file://`
+    /// endpoint from a request must lie under the configured fixture root.
+    pub fn mainnet_client(&self, endpoint: Option<&str>) -> Result<MainnetClient, String> {
+        let endpoint = match endpoint {
+            Some(endpoint) => {
+                check_fixture_endpoint(endpoint, &self.config.rpc_fixture_root)?;
+                endpoint
+            }
+            None => &self.config.rpc_url,
+        };
+
+        let client = MainnetClient::open(endpoint, self.config.rpc)?.with_cache(self.account_cache.clone());
+
+        Ok(match &self.recorder {
+            Some(recorder) => client.with_recorder(recorder.clone()),
+            None => client,
+        })
+    }
+
+    /// JSON-RPC URL that serves a single fork
+    pub fn rpc_url(&self, fork_id: &str) -> String {
+        format!("{}/rpc/fork/{}", self.public_url, fork_id)
+    }
+}
+
+/// Turn a bind address into a URL clients can connect to.
+/// Wildcard addresses (0.0.0.0, ::) are advertised as localhost.
+fn public_url_for(server_addr: &str) -> String {
+    match server_addr.parse::<SocketAddr>() {
+        Ok(addr) if addr.ip().is_unspecified() => format!("http://localhost:{}", addr.port()),
+        Ok(addr) => format!("http://{}", addr),
+        Err(_) => format!("http://{}", server_addr),
+    }
+}
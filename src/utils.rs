@@ -1,10 +1,26 @@
+use base64::{Engine as _, engine::general_purpose};
+use serde_json::{json, Value};
+use solana_account::Account;
 use solana_keypair::Keypair;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_pubkey::Pubkey;
+use spl_token::solana_program::program_pack::Pack;
+use spl_token_2022_interface::extension::{
+    immutable_owner::ImmutableOwner,
+    non_transferable::NonTransferableAccount,
+    pausable::PausableAccount,
+    transfer_fee::TransferFeeAmount,
+    transfer_hook::TransferHookAccount,
+    BaseState, BaseStateWithExtensions, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensions,
+    StateWithExtensionsMut,
+};
+use spl_token_2022_interface::state::{Account as TokenAccount, Mint};
 use std::error::Error;
 
 use axum::http::StatusCode;
 use crate::services::fork_manager::ForkManager;
 
-use crate::constants::LAMPORTS_PER_SOL;
+use crate::constants::{BPF_LOADER_UPGRADEABLE_ID, LAMPORTS_PER_SOL};
 
 /// Parse keypair from various string formats
 /// Supports:
@@ -28,7 +44,8 @@ pub fn parse_keypair(key_str: &str) -> Result<Keypair, Box<dyn Error>> {
     }
 
     // Try to parse as base58
-    Ok(Keypair::from_base58_string(key_str))
+    let bytes = bs58::decode(key_str).into_vec()?;
+    Ok(Keypair::try_from(&bytes[..])?)
 }
 
 pub fn lamports_to_sol(lamports: u64) -> f64 {
@@ -46,11 +63,178 @@ pub fn resolve_fork_id(
     if let Some(uid) = user_id {
         // Try to find fork by user_id
         if let Some(fork_id) = fork_manager.get_user_fork_id(uid) {
-            Ok(fork_id.clone())
+            Ok(fork_id)
         } else {
             Err(StatusCode::NOT_FOUND)
         }
     } else {
         Err(StatusCode::BAD_REQUEST)
     }
-}
\ No newline at end of file
+}
+
+/// Resolve the fork a request addresses: `fork_id` when given, otherwise the
+/// current fork of `user_id`
+pub fn resolve_fork_or_user_id(
+    fork_manager: &ForkManager,
+    fork_id: Option<String>,
+    user_id: Option<String>,
+) -> Result<String, StatusCode> {
+    if let Some(fork_id) = fork_id {
+        Ok(fork_id)
+    } else if let Some(user_id) = user_id {
+        fork_manager
+            .get_user_fork_id(&user_id)
+            .ok_or(StatusCode::NOT_FOUND)
+    } else {
+        Err(StatusCode::BAD_REQUEST)
+    }
+}
+
+/// Decode the upgradeable loader state of an account owned by the BPF Upgradeable Loader.
+/// Returns `None` for accounts owned by any other program.
+pub fn get_upgradeable_loader_state(account: &Account) -> Option<UpgradeableLoaderState> {
+    if account.owner != BPF_LOADER_UPGRADEABLE_ID {
+        return None;
+    }
+
+    bincode::deserialize(&account.data).ok()
+}
+
+/// Encode an account the way Solana JSON-RPC returns it
+pub fn account_to_json(account: &Account, encoding: &str) -> Value {
+    let (encoded_data, encoding) = match encoding {
+        "base58" => (bs58::encode(&account.data).into_string(), "base58"),
+        _ => (general_purpose::STANDARD.encode(&account.data), "base64"),
+    };
+
+    json!({
+        "lamports": account.lamports,
+        "owner": account.owner.to_string(),
+        "executable": account.executable,
+        "rentEpoch": account.rent_epoch,
+        "space": account.data.len(),
+        "data": [encoded_data, encoding]
+    })
+}
+
+/// Decode account data given as base64, base58 or hex (with or without `0x`)
+pub fn decode_account_data(encoded: &str, encoding: &str) -> Result<Vec<u8>, String> {
+    match encoding {
+        "base64" => general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("Invalid base64 data: {}", e)),
+        "base58" => bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| format!("Invalid base58 data: {}", e)),
+        "hex" => decode_hex(encoded.strip_prefix("0x").unwrap_or(encoded)),
+        _ => Err(format!("Unsupported data encoding: {}", encoding)),
+    }
+}
+
+fn decode_hex(encoded: &str) -> Result<Vec<u8>, String> {
+    if !encoded.len().is_multiple_of(2) {
+        return Err("Invalid hex data: odd number of digits".to_string());
+    }
+
+    (0..encoded.len())
+        .step_by(2)
+        .map(|i| {
+            encoded
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| format!("Invalid hex data at offset {}", i))
+        })
+        .collect()
+}
+
+/// Whether `program_id` is the SPL Token or the Token-2022 program
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == spl_token::id() || *program_id == spl_token_2022_interface::id()
+}
+
+/// Whether `mint` is the wrapped SOL mint of either token program
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::id() || *mint == spl_token_2022_interface::native_mint::id()
+}
+
+/// Decode the base state of an SPL Token or Token-2022 account, or `None` if
+/// `account` is not one. Token-2022 extensions are skipped.
+pub fn unpack_token_account(account: &Account) -> Option<TokenAccount> {
+    if !is_token_program(&account.owner) {
+        return None;
+    }
+
+    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .ok()
+        .map(|state| state.base)
+}
+
+/// Decode the base state of an SPL Token or Token-2022 mint, or `None` if
+/// `account` is not one. Token-2022 extensions are skipped.
+pub fn unpack_mint(account: &Account) -> Option<Mint> {
+    if !is_token_program(&account.owner) {
+        return None;
+    }
+
+    StateWithExtensions::<Mint>::unpack(&account.data)
+        .ok()
+        .map(|state| state.base)
+}
+
+/// Extension types of a Token-2022 account or mint; empty for legacy accounts
+pub fn token_extension_types<S: BaseState + Pack>(account: &Account) -> Vec<ExtensionType> {
+    if account.owner != spl_token_2022_interface::id() {
+        return Vec::new();
+    }
+
+    StateWithExtensions::<S>::unpack(&account.data)
+        .and_then(|state| state.get_extension_types())
+        .unwrap_or_default()
+}
+
+/// Pack a new token account for `program_id`. Token-2022 accounts get the
+/// extensions their mint requires, plus `ImmutableOwner` like an ATA would.
+pub fn pack_token_account(
+    program_id: &Pubkey,
+    state: TokenAccount,
+    mint_extension_types: &[ExtensionType],
+) -> Result<Vec<u8>, String> {
+    if *program_id != spl_token_2022_interface::id() {
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(state, &mut data).map_err(|e| format!("Failed to pack token account: {:?}", e))?;
+        return Ok(data);
+    }
+
+    let mut extension_types = ExtensionType::get_required_init_account_extensions(mint_extension_types);
+    if !extension_types.contains(&ExtensionType::ImmutableOwner) {
+        extension_types.push(ExtensionType::ImmutableOwner);
+    }
+
+    let len = ExtensionType::try_calculate_account_len::<TokenAccount>(&extension_types)
+        .map_err(|e| format!("Failed to size token account: {:?}", e))?;
+    let mut data = vec![0; len];
+    let mut account = StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut data)
+        .map_err(|e| format!("Failed to pack token account: {:?}", e))?;
+
+    for extension_type in extension_types {
+        let result = match extension_type {
+            ExtensionType::ImmutableOwner => account.init_extension::<ImmutableOwner>(true).map(|_| ()),
+            ExtensionType::TransferFeeAmount => account.init_extension::<TransferFeeAmount>(true).map(|_| ()),
+            ExtensionType::TransferHookAccount => account.init_extension::<TransferHookAccount>(true).map(|_| ()),
+            ExtensionType::NonTransferableAccount => {
+                account.init_extension::<NonTransferableAccount>(true).map(|_| ())
+            }
+            ExtensionType::PausableAccount => account.init_extension::<PausableAccount>(true).map(|_| ()),
+            other => return Err(format!("Unsupported token account extension {:?}", other)),
+        };
+        result.map_err(|e| format!("Failed to initialize {:?}: {:?}", extension_type, e))?;
+    }
+
+    account.base = state;
+    account.pack_base();
+    account
+        .init_account_type()
+        .map_err(|e| format!("Failed to pack token account: {:?}", e))?;
+
+    Ok(data)
+}
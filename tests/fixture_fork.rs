@@ -0,0 +1,65 @@
+//! End-to-end fork against the recorded fixtures in `tests/fixtures/mainnet`
+
+use std::path::Path;
+use std::str::FromStr;
+
+use solana_fork_engine::{load_accounts, load_transaction_accounts, ForkManager, MainnetClient, RpcSettings, FIXTURE_SCHEME};
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_system_interface::instruction as system_instruction;
+use solana_transaction::Transaction;
+
+/// Fixture wallet holding 1,000 SOL
+const WHALE: &str = "2QNQd26hRg8Pz41vNyxhxyckZ8LLTx5oLaBfjBVjqP4f";
+/// Fixture wallet holding 25 SOL
+const TREASURY: &str = "2szdDLmXebCvRt1DtEoPgNQa1ZCz2vqrawRu6K6f9qyC";
+
+fn fixture_client() -> MainnetClient {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mainnet");
+    MainnetClient::open(&format!("{}{}", FIXTURE_SCHEME, dir.display()), RpcSettings::default()).unwrap()
+}
+
+#[tokio::test]
+async fn fork_from_fixtures_loads_accounts_lazily() {
+    let manager = ForkManager::new();
+    let fork_id = manager
+        .create_fork_with_mainnet_sync("fixture-user".to_string(), &fixture_client(), true)
+        .await
+        .unwrap();
+    let shared_fork = manager.get_fork(&fork_id).unwrap();
+
+    let whale = Pubkey::from_str(WHALE).unwrap();
+    let treasury = Pubkey::from_str(TREASURY).unwrap();
+    let unknown = Pubkey::new_unique();
+
+    // Accounts are pulled in from the fixture before the fork is locked
+    load_accounts(&shared_fork, &[whale, unknown]).await.unwrap();
+    let mut fork = shared_fork.write().await;
+    assert_eq!(fork.get_balance(&whale), 1_000_000_000_000);
+    // Accounts missing from the fixture do not exist
+    assert!(fork.get_account(&unknown).is_none());
+    // The treasury is only loaded when a transaction references it
+    assert!(fork.get_account(&treasury).is_none());
+
+    let payer = Keypair::new();
+    fork.add_balance(&payer.pubkey(), 2_000_000_000).unwrap();
+    let blockhash = fork.latest_blockhash();
+    drop(fork);
+
+    let message = Message::new_with_blockhash(
+        &[system_instruction::transfer(&payer.pubkey(), &treasury, 1_000_000_000)],
+        Some(&payer.pubkey()),
+        &blockhash,
+    );
+    let transaction = Transaction::new(&[&payer], message, blockhash);
+    load_transaction_accounts(&shared_fork, &transaction.clone().into()).await.unwrap();
+
+    let mut fork = shared_fork.write().await;
+    let result = fork.send_transaction(transaction, false).unwrap();
+
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(fork.get_balance(&treasury), 26_000_000_000);
+    assert!(fork.history.get(&result.signature).is_some());
+}